@@ -0,0 +1,35 @@
+//! Génère les typings TypeScript du protocole WebSocket sur la sortie standard
+//!
+//! `cargo run -p zeta-proto --features typescript --bin ws-typings > static/zeta_protocol.d.ts`
+
+use ts_rs::TS;
+use zeta_proto::ws::{ClientFrame, ErrorCode, FeedMode, LimitScope, PostPayload, ServerFrame};
+use zeta_proto::{Attachment, DirectMessage, Follow, PeerInfo, Post, PostDelete, PostEdit, Profile, Reaction, RelayInfo, ThreadNode};
+
+fn main() {
+    let decls = [
+        Attachment::decl(),
+        Post::decl(),
+        PostEdit::decl(),
+        PostDelete::decl(),
+        PeerInfo::decl(),
+        RelayInfo::decl(),
+        ThreadNode::decl(),
+        Reaction::decl(),
+        Follow::decl(),
+        FeedMode::decl(),
+        DirectMessage::decl(),
+        Profile::decl(),
+        ErrorCode::decl(),
+        LimitScope::decl(),
+        ServerFrame::decl(),
+        PostPayload::decl(),
+        ClientFrame::decl(),
+    ];
+    println!("// Généré par `cargo run -p zeta-proto --features typescript --bin ws-typings`, ne pas modifier.");
+    println!("// Protocole WebSocket entre un relay Zeta2 et ses clients navigateur.");
+    for decl in decls {
+        println!();
+        println!("export {}", decl);
+    }
+}
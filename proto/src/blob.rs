@@ -0,0 +1,174 @@
+//! Pièces jointes adressées par leur contenu
+//!
+//! Un post ne transporte pas ses médias : il référence chaque fichier par son
+//! identifiant de contenu (CID, empreinte SHA-256 en hex). Les relais gardent
+//! les fichiers et les servent en HTTP ; les nœuds natifs récupèrent ceux qui
+//! leur manquent auprès de leurs pairs avec le protocole d'échange de blocs.
+//! Le type MIME est toujours déduit des octets, jamais de ce que déclare
+//! l'expéditeur.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Taille maximale (octets) d'un fichier joint ; encodé en hex, un bloc
+/// tient dans une réponse request-response (10 Mio)
+pub const MAX_BLOB_BYTES: usize = 4 * 1024 * 1024;
+
+/// Nombre maximum de pièces jointes par post
+pub const MAX_ATTACHMENTS: usize = 4;
+
+/// Longueur maximale (caractères) du nom d'un fichier joint
+pub const MAX_ATTACHMENT_NAME_CHARS: usize = 128;
+
+/// Fichier joint à un post
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Attachment {
+    /// Empreinte SHA-256 (hex) du contenu
+    pub cid: String,
+    /// Type MIME déduit du contenu
+    pub mime: String,
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub size: u64,
+    /// Nom d'origine du fichier, purement indicatif
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub name: Option<String>,
+}
+
+impl Attachment {
+    /// Décrit un contenu, `None` s'il est trop gros ou d'un type refusé
+    pub fn describe(bytes: &[u8], name: Option<String>) -> Option<Self> {
+        if bytes.len() > MAX_BLOB_BYTES {
+            return None;
+        }
+        Some(Self {
+            cid: cid(bytes),
+            mime: sniff_mime(bytes)?.to_string(),
+            size: bytes.len() as u64,
+            name,
+        })
+    }
+
+    /// Encodage canonique, inclus dans la signature du post
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        crate::signing::canonical_bytes("zeta2-attachment-v1", &[
+            self.cid.as_bytes(),
+            self.mime.as_bytes(),
+            &self.size.to_be_bytes(),
+            self.name.as_deref().unwrap_or_default().as_bytes(),
+        ])
+    }
+
+    /// Vérifie la forme de la référence (le contenu est vérifié à la réception du bloc)
+    pub fn is_valid(&self) -> bool {
+        is_valid_cid(&self.cid)
+            && ALLOWED_MIME_TYPES.contains(&self.mime.as_str())
+            && self.size as usize <= MAX_BLOB_BYTES
+            && self.name.as_deref().is_none_or(|name| {
+                !name.is_empty()
+                    && name.chars().count() <= MAX_ATTACHMENT_NAME_CHARS
+                    && !name.chars().any(|c| c.is_control() || c == '/' || c == '\\')
+            })
+    }
+}
+
+/// Identifiant de contenu d'un fichier
+pub fn cid(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Indique si une chaîne est un CID bien formé (64 caractères hex minuscules)
+pub fn is_valid_cid(cid: &str) -> bool {
+    cid.len() == 64 && cid.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// Types MIME acceptés pour les pièces jointes
+///
+/// HTML et SVG sont exclus : servis depuis l'origine du relay, ils pourraient
+/// exécuter du script dans l'interface.
+pub const ALLOWED_MIME_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "video/mp4",
+    "video/webm",
+    "audio/mpeg",
+    "audio/ogg",
+    "application/pdf",
+    "text/plain",
+];
+
+/// Marques majeures d'un conteneur `ftyp` reconnues comme MP4 ; les autres
+/// (HEIC, QuickTime, AVIF…) partagent la même en-tête mais pas le format
+const MP4_BRANDS: &[&[u8; 4]] = &[
+    b"isom", b"iso2", b"iso4", b"iso5", b"iso6", b"mp41", b"mp42", b"avc1", b"M4V ", b"dash",
+];
+
+/// Type MIME d'un contenu d'après ses premiers octets, `None` s'il n'est pas accepté
+pub fn sniff_mime(bytes: &[u8]) -> Option<&'static str> {
+    let mime = match bytes {
+        [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, ..] => "image/png",
+        [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] => {
+            if !brand.get(..4).is_some_and(|brand| MP4_BRANDS.iter().any(|mp4| mp4.as_slice() == brand)) {
+                return None;
+            }
+            "video/mp4"
+        }
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => "video/webm",
+        [b'I', b'D', b'3', ..] | [0xff, 0xfb | 0xf3 | 0xf2, ..] => "audio/mpeg",
+        [b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+        [b'%', b'P', b'D', b'F', b'-', ..] => "application/pdf",
+        _ => {
+            // Texte brut : UTF-8 valide, sans octet nul ni balisage en tête
+            let text = std::str::from_utf8(bytes).ok()?;
+            if text.contains('\0') || text.trim_start().starts_with('<') {
+                return None;
+            }
+            "text/plain"
+        }
+    };
+    Some(mime)
+}
+
+/// Requête du protocole d'échange de blocs `/zeta2/blocks/1.0.0`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRequest {
+    pub cid: String,
+}
+
+/// Réponse à une `BlockRequest` : le contenu en hex, absent si le pair ne l'a pas
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockResponse {
+    pub cid: String,
+    pub data: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0, 0x18];
+        bytes.extend_from_slice(b"ftyp");
+        bytes.extend_from_slice(brand);
+        bytes.extend_from_slice(&[0, 0, 2, 0]);
+        bytes
+    }
+
+    #[test]
+    fn mp4_is_recognised_by_its_major_brand() {
+        for brand in [b"isom", b"mp42", b"avc1"] {
+            assert_eq!(sniff_mime(&ftyp(brand)), Some("video/mp4"));
+        }
+        // Même en-tête, autres formats
+        for brand in [b"heic", b"mif1", b"qt  ", b"avif"] {
+            assert_eq!(sniff_mime(&ftyp(brand)), None);
+        }
+        assert_eq!(sniff_mime(b"\0\0\0\x08ftyp"), None);
+    }
+}
@@ -0,0 +1,37 @@
+//! Canaux de discussion (hashtags, communautés)
+//!
+//! Chaque post appartient à un canal. Le canal par défaut circule sur le
+//! topic gossipsub de base, ce qui garde compatibles les nœuds antérieurs ;
+//! les autres canaux ont chacun leur topic, auquel un nœud ne s'abonne que
+//! si lui-même ou l'un de ses navigateurs le suit.
+
+/// Canal des posts qui n'en déclarent pas
+pub const DEFAULT_CHANNEL: &str = "general";
+
+/// Longueur maximale d'un nom de canal
+pub const MAX_CHANNEL_LEN: usize = 32;
+
+/// Nom de canal normalisé (`#Rust` -> `rust`), `None` s'il est invalide
+///
+/// Un nom valide ne contient que des lettres ASCII, des chiffres, `-` et `_`.
+pub fn normalize(name: &str) -> Option<String> {
+    let name = name.trim().trim_start_matches('#').to_ascii_lowercase();
+    let valid = !name.is_empty()
+        && name.len() <= MAX_CHANNEL_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(name)
+}
+
+/// Indique si un nom de canal est déjà sous forme normalisée
+pub fn is_valid(name: &str) -> bool {
+    normalize(name).as_deref() == Some(name)
+}
+
+/// Topic gossipsub d'un canal
+pub fn topic(base: &str, channel: &str) -> String {
+    if channel == DEFAULT_CHANNEL {
+        base.to_string()
+    } else {
+        format!("{}/{}", base, channel)
+    }
+}
@@ -0,0 +1,141 @@
+//! Messages directs chiffrés de bout en bout
+//!
+//! Chaque identité dérive une clé X25519 de sa clé ed25519 (forme de
+//! Montgomery de la clé publique, scalaire de la clé privée) : la clé de
+//! chiffrement d'un destinataire se lit donc dans son PeerId. L'échange
+//! Diffie-Hellman entre expéditeur et destinataire donne, via HKDF-SHA256, la
+//! clé ChaCha20-Poly1305 du message.
+//!
+//! Les relais ne voient que l'expéditeur, le destinataire et la date : ils
+//! vérifient la signature de l'expéditeur et routent le message sans pouvoir
+//! le lire.
+
+use crate::signing;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// Taille maximale (octets) du texte d'un message direct
+pub const MAX_DIRECT_BYTES: usize = 4096;
+
+/// Tag d'authentification ajouté par ChaCha20-Poly1305
+const TAG_BYTES: usize = 16;
+
+/// Message direct chiffré pour un destinataire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct DirectMessage {
+    /// PeerId de l'expéditeur, qui contient sa clé publique
+    pub sender: String,
+    /// PeerId du destinataire, dont est dérivée la clé de chiffrement
+    pub recipient: String,
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub timestamp: i64,
+    /// Nonce aléatoire (hex, 12 octets), unique par message
+    pub nonce: String,
+    /// Texte chiffré et tag d'authentification (hex)
+    pub ciphertext: String,
+    /// Signature ed25519 de l'expéditeur (hex)
+    pub signature: String,
+}
+
+impl DirectMessage {
+    /// Chiffre un texte pour `recipient` ; `None` si le PeerId ne contient
+    /// pas de clé ed25519 ou si le texte est trop long
+    pub fn seal(secret_key: &[u8; 32], recipient: String, content: &str, timestamp: i64) -> Option<Self> {
+        if content.len() > MAX_DIRECT_BYTES {
+            return None;
+        }
+        let mut nonce = [0u8; 12];
+        getrandom::getrandom(&mut nonce).ok()?;
+
+        let mut message = Self {
+            sender: signing::peer_id_from_secret_key(secret_key),
+            recipient,
+            timestamp,
+            nonce: hex::encode(nonce),
+            ciphertext: String::new(),
+            signature: String::new(),
+        };
+        let cipher = cipher(secret_key, &message.recipient)?;
+        let payload = Payload { msg: content.as_bytes(), aad: &message.associated_data() };
+        let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), payload).ok()?;
+        message.ciphertext = hex::encode(ciphertext);
+        message.signature = signing::sign(secret_key, &message.signing_bytes());
+        Some(message)
+    }
+
+    /// Déchiffre le message avec la clé de l'expéditeur ou du destinataire
+    pub fn open(&self, secret_key: &[u8; 32]) -> Option<String> {
+        let me = signing::peer_id_from_secret_key(secret_key);
+        let peer = if me == self.recipient {
+            &self.sender
+        } else if me == self.sender {
+            &self.recipient
+        } else {
+            return None;
+        };
+        let nonce = <[u8; 12]>::try_from(hex::decode(&self.nonce).ok()?).ok()?;
+        let ciphertext = hex::decode(&self.ciphertext).ok()?;
+        let payload = Payload { msg: &ciphertext, aad: &self.associated_data() };
+        let plaintext = cipher(secret_key, peer)?.decrypt(Nonce::from_slice(&nonce), payload).ok()?;
+        String::from_utf8(plaintext).ok()
+    }
+
+    /// Identifiant du message : son nonce, tiré au hasard
+    pub fn id(&self) -> &str {
+        &self.nonce
+    }
+
+    /// Métadonnées authentifiées par le chiffrement
+    fn associated_data(&self) -> Vec<u8> {
+        signing::canonical_bytes("zeta2-direct-aad-v1", &[
+            self.sender.as_bytes(),
+            self.recipient.as_bytes(),
+            &self.timestamp.to_be_bytes(),
+        ])
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        signing::canonical_bytes("zeta2-direct-v1", &[
+            self.sender.as_bytes(),
+            self.recipient.as_bytes(),
+            &self.timestamp.to_be_bytes(),
+            self.nonce.as_bytes(),
+            self.ciphertext.as_bytes(),
+        ])
+    }
+
+    /// Vérifie la forme du message et la signature de l'expéditeur, sans le déchiffrer
+    pub fn verify(&self) -> bool {
+        let well_formed = self.nonce.len() == 24
+            && self.ciphertext.len() >= 2 * TAG_BYTES
+            && self.ciphertext.len() <= 2 * (MAX_DIRECT_BYTES + TAG_BYTES)
+            && x25519_public_key(&self.recipient).is_some();
+        well_formed && signing::verify_by_peer_id(&self.sender, &self.signature, &self.signing_bytes())
+    }
+}
+
+/// Clé publique X25519 dérivée de la clé ed25519 contenue dans un PeerId
+pub fn x25519_public_key(peer_id: &str) -> Option<[u8; 32]> {
+    let public_key = hex::decode(signing::public_key_hex_from_peer_id(peer_id)?).ok()?;
+    let public_key = ed25519_dalek::VerifyingKey::try_from(public_key.as_slice()).ok()?;
+    Some(public_key.to_montgomery().to_bytes())
+}
+
+/// Chiffrement symétrique partagé entre notre clé et celle d'un autre PeerId
+fn cipher(secret_key: &[u8; 32], peer_id: &str) -> Option<ChaCha20Poly1305> {
+    let public_key = hex::decode(signing::public_key_hex_from_peer_id(peer_id)?).ok()?;
+    let public_key = ed25519_dalek::VerifyingKey::try_from(public_key.as_slice()).ok()?;
+    let scalar = ed25519_dalek::SigningKey::from_bytes(secret_key).to_scalar_bytes();
+    let shared = public_key.to_montgomery().mul_clamped(scalar).to_bytes();
+    // Une clé d'ordre faible donnerait un secret nul, connu de tous
+    if shared == [0u8; 32] {
+        return None;
+    }
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, &shared).expand(b"zeta2-direct-key-v1", &mut key).ok()?;
+    Some(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
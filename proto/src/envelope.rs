@@ -0,0 +1,188 @@
+//! Enveloppe versionnée des messages gossipsub
+//!
+//! Chaque message publié sur le topic est encapsulé dans
+//! `{"version", "kind", "payload"}`. Un nœud qui ne connaît pas un type (ou
+//! reçoit une version plus récente) laisse gossipsub le relayer sans
+//! l'interpréter : on peut ajouter des messages sans mettre à jour tout le
+//! réseau en même temps.
+//!
+//! Les nœuds publient l'enveloppe en JSON. Le décodage accepte encore le
+//! CBOR des nœuds qui l'émettaient, reconnu au premier octet : une map CBOR
+//! commence par `0xA0..=0xBF`, jamais par un caractère JSON. Il n'est plus
+//! émis : un message traverse plusieurs sauts, et un nœud qui ne le lit pas
+//! le rejette et pénalise le pair qui le lui a relayé.
+
+use crate::NetworkMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Version du protocole gossip, incrémentée uniquement pour les changements incompatibles
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// Version annoncée par identify
+pub const IDENTIFY_PROTOCOL_VERSION: &str = "/zeta2/1.0.0";
+
+/// Encodage d'une enveloppe reçue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    /// Émis par d'anciens nœuds, seulement décodé
+    Cbor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u16,
+    pub kind: String,
+    #[serde(default)]
+    pub payload: Value,
+}
+
+/// Message reçu après ouverture de l'enveloppe
+#[derive(Debug, Clone)]
+pub enum Decoded {
+    Message(Box<NetworkMessage>),
+    /// Type inconnu ou version plus récente : relayé sans être interprété
+    Unknown { version: u16, kind: String },
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Les octets ne forment pas une enveloppe
+    Envelope(String),
+    /// Type connu mais contenu invalide
+    Payload { kind: String, error: serde_json::Error },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Envelope(e) => write!(f, "enveloppe invalide: {}", e),
+            DecodeError::Payload { kind, error } => write!(f, "contenu {} invalide: {}", kind, error),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Envelope {
+    /// Encapsule un message dans la version courante du protocole
+    pub fn wrap(msg: &NetworkMessage) -> Self {
+        let kind = msg.kind().to_string();
+        // Représentation externe de serde : `{"Kind": contenu}` ou `"Kind"`
+        let payload = match serde_json::to_value(msg) {
+            Ok(Value::Object(mut map)) => map.remove(&kind).unwrap_or(Value::Null),
+            _ => Value::Null,
+        };
+        Self { version: PROTOCOL_VERSION, kind, payload }
+    }
+
+    /// Interprète le contenu si le type et la version sont connus
+    pub fn open(self) -> Result<Decoded, DecodeError> {
+        if self.version > PROTOCOL_VERSION || !NetworkMessage::is_known_kind(&self.kind) {
+            return Ok(Decoded::Unknown { version: self.version, kind: self.kind });
+        }
+        let mut tagged = serde_json::Map::new();
+        tagged.insert(self.kind.clone(), self.payload);
+        serde_json::from_value(Value::Object(tagged))
+            .map(|msg| Decoded::Message(Box::new(msg)))
+            .map_err(|error| DecodeError::Payload { kind: self.kind, error })
+    }
+}
+
+/// Sérialise un message en JSON pour publication sur le topic
+pub fn encode(msg: &NetworkMessage) -> Vec<u8> {
+    serde_json::to_vec(&Envelope::wrap(msg)).expect("Envelope sérialisable")
+}
+
+/// Encodage d'un message reçu, d'après son premier octet
+pub fn detect_encoding(bytes: &[u8]) -> Encoding {
+    match bytes.first() {
+        Some(0xa0..=0xbf) => Encoding::Cbor,
+        _ => Encoding::Json,
+    }
+}
+
+/// Décode un message reçu du topic, quel que soit son encodage
+///
+/// Les messages JSON sans enveloppe des nœuds antérieurs sont encore acceptés
+/// pendant la migration.
+pub fn decode(bytes: &[u8]) -> Result<Decoded, DecodeError> {
+    match detect_encoding(bytes) {
+        Encoding::Cbor => ciborium::from_reader::<Envelope, _>(bytes)
+            .map_err(|e| DecodeError::Envelope(e.to_string()))?
+            .open(),
+        Encoding::Json => match serde_json::from_slice::<Envelope>(bytes) {
+            Ok(envelope) => envelope.open(),
+            Err(e) => serde_json::from_slice::<NetworkMessage>(bytes)
+                .map(|msg| Decoded::Message(Box::new(msg)))
+                .map_err(|_| DecodeError::Envelope(e.to_string())),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message() -> NetworkMessage {
+        NetworkMessage::PeerJoined { peer_id: "12D3KooW".to_string(), name: "Alice".to_string() }
+    }
+
+    fn decoded_peer(decoded: Decoded) -> String {
+        match decoded {
+            Decoded::Message(msg) => match *msg {
+                NetworkMessage::PeerJoined { peer_id, .. } => peer_id,
+                other => panic!("message inattendu: {:?}", other),
+            },
+            Decoded::Unknown { kind, .. } => panic!("type inconnu: {}", kind),
+        }
+    }
+
+    #[test]
+    fn json_envelope_round_trips() {
+        let bytes = encode(&message());
+        assert_eq!(detect_encoding(&bytes), Encoding::Json);
+        let envelope: Envelope = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!((envelope.version, envelope.kind.as_str()), (PROTOCOL_VERSION, "PeerJoined"));
+        assert_eq!(decoded_peer(decode(&bytes).unwrap()), "12D3KooW");
+    }
+
+    #[test]
+    fn cbor_envelope_is_still_decoded() {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&Envelope::wrap(&message()), &mut bytes).unwrap();
+        assert_eq!(detect_encoding(&bytes), Encoding::Cbor);
+        assert_eq!(decoded_peer(decode(&bytes).unwrap()), "12D3KooW");
+        assert!(matches!(decode(&[0xa1, 0xff]), Err(DecodeError::Envelope(_))));
+    }
+
+    #[test]
+    fn unit_variant_round_trips() {
+        let bytes = encode(&NetworkMessage::Heartbeat);
+        assert!(matches!(decode(&bytes).unwrap(), Decoded::Message(msg) if matches!(*msg, NetworkMessage::Heartbeat)));
+    }
+
+    #[test]
+    fn legacy_unversioned_json_is_decoded() {
+        let bytes = serde_json::to_vec(&message()).unwrap();
+        assert_eq!(decoded_peer(decode(&bytes).unwrap()), "12D3KooW");
+    }
+
+    #[test]
+    fn unknown_kind_or_newer_version_is_passed_through() {
+        let bytes = br#"{"version":1,"kind":"Poll","payload":{"question":"?"}}"#;
+        assert!(matches!(decode(bytes).unwrap(), Decoded::Unknown { version: 1, kind } if kind == "Poll"));
+
+        let bytes = br#"{"version":2,"kind":"Post","payload":{"format":"nouveau"}}"#;
+        assert!(matches!(decode(bytes).unwrap(), Decoded::Unknown { version: 2, kind } if kind == "Post"));
+    }
+
+    #[test]
+    fn invalid_bytes_are_errors() {
+        assert!(matches!(decode(b"pas du json"), Err(DecodeError::Envelope(_))));
+        let bytes = br#"{"version":1,"kind":"PeerJoined","payload":{"name":3}}"#;
+        assert!(matches!(decode(bytes), Err(DecodeError::Payload { kind, .. }) if kind == "PeerJoined"));
+    }
+}
@@ -0,0 +1,757 @@
+//! Zeta Network - Types du protocole
+//!
+//! Chaque type échangé sur le réseau (gossipsub, synchronisation, WebSocket)
+//! est défini une seule fois ici et partagé par le nœud natif `zeta2` et le
+//! nœud navigateur `zeta-wasm`.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub mod blob;
+pub mod channel;
+pub mod direct;
+pub mod envelope;
+pub mod signing;
+pub mod validation;
+pub mod ws;
+
+pub use blob::Attachment;
+pub use direct::DirectMessage;
+
+/// Longueur maximale d'un post, en graphèmes (un emoji composé compte pour un)
+pub const MAX_POST_GRAPHEMES: usize = 280;
+
+/// Longueur d'un texte telle que la perçoit le lecteur
+pub fn grapheme_count(text: &str) -> usize {
+    text.graphemes(true).count()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Post {
+    pub id: String,
+    pub author: String,
+    pub author_name: String,
+    pub content: String,
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub timestamp: i64,
+    /// Clé publique ed25519 de l'auteur (hex)
+    #[serde(default)]
+    pub public_key: String,
+    /// Signature ed25519 (hex) de l'encodage canonique du post
+    #[serde(default)]
+    pub signature: String,
+    /// Post auquel celui-ci répond
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub reply_to: Option<String>,
+    /// Premier post de la conversation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub thread_root: Option<String>,
+    /// Dernière modification signée par l'auteur, hors signature du post
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub edit: Option<Box<PostEdit>>,
+    /// Canal du post, `general` s'il est absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub channel: Option<String>,
+    /// Fichiers joints, référencés par leur contenu
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "typescript", ts(as = "Option<Vec<Attachment>>", optional))]
+    pub attachments: Vec<Attachment>,
+}
+
+impl Post {
+    /// Encodage canonique des champs couverts par la signature
+    ///
+    /// Les champs de conversation ne sont ajoutés que pour une réponse ou un
+    /// post de canal, et le canal seulement s'il est déclaré : les signatures
+    /// des posts antérieurs restent donc valides. Un post avec pièces jointes
+    /// déclare tous ces champs, vides au besoin, avant ses pièces jointes.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let timestamp = self.timestamp.to_be_bytes();
+        let attachments: Vec<Vec<u8>> = self.attachments.iter().map(Attachment::signing_bytes).collect();
+        let has_attachments = !attachments.is_empty();
+        let mut fields: Vec<&[u8]> = vec![
+            self.id.as_bytes(),
+            self.author.as_bytes(),
+            self.author_name.as_bytes(),
+            self.content.as_bytes(),
+            &timestamp,
+            self.public_key.as_bytes(),
+        ];
+        if self.reply_to.is_some() || self.thread_root.is_some() || self.channel.is_some() || has_attachments {
+            fields.push(self.reply_to.as_deref().unwrap_or_default().as_bytes());
+            fields.push(self.thread_root.as_deref().unwrap_or_default().as_bytes());
+        }
+        if self.channel.is_some() || has_attachments {
+            fields.push(self.channel.as_deref().unwrap_or_default().as_bytes());
+        }
+        fields.extend(attachments.iter().map(Vec::as_slice));
+        signing::canonical_bytes("zeta2-post-v1", &fields)
+    }
+
+    /// Renseigne la clé publique puis signe le post
+    pub fn sign(&mut self, secret_key: &[u8; 32]) {
+        self.public_key = signing::public_key_hex(secret_key);
+        self.signature = signing::sign(secret_key, &self.signing_bytes());
+    }
+
+    /// Empreinte du contenu (auteur, texte, date), indépendante de l'identifiant
+    pub fn content_hash(&self) -> String {
+        let bytes = signing::canonical_bytes("zeta2-post-content-v1", &[
+            self.author.as_bytes(),
+            self.content.as_bytes(),
+            &self.timestamp.to_be_bytes(),
+        ]);
+        hex::encode(Sha256::digest(bytes))
+    }
+
+    /// Identifiant de la conversation à laquelle appartient le post
+    pub fn thread_id(&self) -> &str {
+        self.thread_root.as_deref().or(self.reply_to.as_deref()).unwrap_or(&self.id)
+    }
+
+    /// Canal auquel appartient le post
+    pub fn channel(&self) -> &str {
+        self.channel.as_deref().unwrap_or(channel::DEFAULT_CHANNEL)
+    }
+
+    /// Texte affiché : celui de la dernière modification s'il y en a une
+    pub fn current_content(&self) -> &str {
+        self.edit.as_ref().map_or(&self.content, |edit| &edit.content)
+    }
+
+    /// Vérifie que l'auteur correspond à la clé publique, que la signature est
+    /// valide, que le texte, le canal et les pièces jointes respectent les
+    /// limites et que l'éventuelle modification vient du même auteur
+    pub fn verify(&self) -> bool {
+        if grapheme_count(&self.content) > MAX_POST_GRAPHEMES {
+            return false;
+        }
+        if self.channel.as_deref().is_some_and(|c| !channel::is_valid(c)) {
+            return false;
+        }
+        if self.attachments.len() > blob::MAX_ATTACHMENTS || !self.attachments.iter().all(Attachment::is_valid) {
+            return false;
+        }
+        let author_matches = signing::peer_id_from_public_key_hex(&self.public_key)
+            .is_some_and(|peer_id| peer_id == self.author);
+        let edit_valid = self.edit.as_ref().is_none_or(|edit| edit.applies_to(self) && edit.verify());
+        author_matches && edit_valid && signing::verify(&self.public_key, &self.signature, &self.signing_bytes())
+    }
+}
+
+/// Modification d'un post par son auteur
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct PostEdit {
+    pub post_id: String,
+    /// Nouveau texte du post
+    pub content: String,
+    /// Date en millisecondes : deux modifications peuvent se suivre de près
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub edited_at: i64,
+    /// PeerId de l'auteur, qui contient sa clé publique
+    pub author: String,
+    pub signature: String,
+}
+
+impl PostEdit {
+    pub fn new(post_id: String, content: String, edited_at: i64, secret_key: &[u8; 32]) -> Self {
+        let mut edit = Self {
+            post_id,
+            content,
+            edited_at,
+            author: signing::peer_id_from_secret_key(secret_key),
+            signature: String::new(),
+        };
+        edit.signature = signing::sign(secret_key, &edit.signing_bytes());
+        edit
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        signing::canonical_bytes("zeta2-edit-v1", &[
+            self.post_id.as_bytes(),
+            self.content.as_bytes(),
+            &self.edited_at.to_be_bytes(),
+            self.author.as_bytes(),
+        ])
+    }
+
+    /// La modification vise ce post et vient de son auteur
+    pub fn applies_to(&self, post: &Post) -> bool {
+        self.post_id == post.id && self.author == post.author
+    }
+
+    /// La modification remplace `current` : la plus récente l'emporte, et à
+    /// date égale la plus grande signature, pour que tous les nœuds retiennent
+    /// la même quel que soit l'ordre d'arrivée
+    pub fn supersedes(&self, current: &PostEdit) -> bool {
+        (self.edited_at, &self.signature) > (current.edited_at, &current.signature)
+    }
+
+    pub fn verify(&self) -> bool {
+        grapheme_count(&self.content) <= MAX_POST_GRAPHEMES
+            && signing::verify_by_peer_id(&self.author, &self.signature, &self.signing_bytes())
+    }
+}
+
+/// Suppression d'un post par son auteur
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct PostDelete {
+    pub post_id: String,
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub deleted_at: i64,
+    /// PeerId de l'auteur, qui contient sa clé publique
+    pub author: String,
+    pub signature: String,
+}
+
+impl PostDelete {
+    pub fn new(post_id: String, deleted_at: i64, secret_key: &[u8; 32]) -> Self {
+        let mut delete = Self {
+            post_id,
+            deleted_at,
+            author: signing::peer_id_from_secret_key(secret_key),
+            signature: String::new(),
+        };
+        delete.signature = signing::sign(secret_key, &delete.signing_bytes());
+        delete
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        signing::canonical_bytes("zeta2-delete-v1", &[
+            self.post_id.as_bytes(),
+            &self.deleted_at.to_be_bytes(),
+            self.author.as_bytes(),
+        ])
+    }
+
+    /// La suppression vise ce post et vient de son auteur
+    pub fn applies_to(&self, post: &Post) -> bool {
+        self.post_id == post.id && self.author == post.author
+    }
+
+    pub fn verify(&self) -> bool {
+        signing::verify_by_peer_id(&self.author, &self.signature, &self.signing_bytes())
+    }
+}
+
+/// Taille maximale (octets) d'une réaction
+pub const MAX_EMOJI_BYTES: usize = 32;
+
+/// Réaction d'une identité à un post
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Reaction {
+    pub post_id: String,
+    pub emoji: String,
+    /// PeerId de l'auteur, qui contient sa clé publique
+    pub author: String,
+    /// Signature ed25519 (hex) de l'encodage canonique de la réaction
+    pub signature: String,
+}
+
+impl Reaction {
+    /// Crée une réaction signée par la clé privée de l'auteur
+    pub fn new(post_id: String, emoji: String, secret_key: &[u8; 32]) -> Self {
+        let mut reaction = Self {
+            post_id,
+            emoji,
+            author: signing::peer_id_from_secret_key(secret_key),
+            signature: String::new(),
+        };
+        reaction.signature = signing::sign(secret_key, &reaction.signing_bytes());
+        reaction
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        signing::canonical_bytes("zeta2-reaction-v1", &[
+            self.post_id.as_bytes(),
+            self.emoji.as_bytes(),
+            self.author.as_bytes(),
+        ])
+    }
+
+    /// Vérifie la forme de l'emoji et la signature de l'auteur
+    pub fn verify(&self) -> bool {
+        let well_formed = !self.emoji.is_empty()
+            && self.emoji.len() <= MAX_EMOJI_BYTES
+            && !self.emoji.chars().any(|c| c.is_whitespace() || c.is_control());
+        well_formed && signing::verify_by_peer_id(&self.author, &self.signature, &self.signing_bytes())
+    }
+}
+
+/// Nombre de réactions par emoji pour un post
+pub type ReactionCounts = BTreeMap<String, usize>;
+
+/// Abonnement d'une identité aux posts d'un auteur, ou désabonnement
+///
+/// Le même contenu sert aux deux messages : le sens est couvert par la
+/// signature, un abonnement ne peut donc pas être rejoué en désabonnement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Follow {
+    /// PeerId de l'abonné, qui contient sa clé publique
+    pub follower: String,
+    /// PeerId de l'auteur suivi
+    pub followee: String,
+    /// Date en millisecondes : un désabonnement peut suivre de près l'abonnement
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub timestamp: i64,
+    pub signature: String,
+}
+
+impl Follow {
+    /// Crée un abonnement (`following`) ou un désabonnement signé par l'abonné
+    pub fn new(followee: String, following: bool, timestamp: i64, secret_key: &[u8; 32]) -> Self {
+        let mut follow = Self {
+            follower: signing::peer_id_from_secret_key(secret_key),
+            followee,
+            timestamp,
+            signature: String::new(),
+        };
+        follow.signature = signing::sign(secret_key, &follow.signing_bytes(following));
+        follow
+    }
+
+    pub fn signing_bytes(&self, following: bool) -> Vec<u8> {
+        let domain = if following { "zeta2-follow-v1" } else { "zeta2-unfollow-v1" };
+        signing::canonical_bytes(domain, &[
+            self.follower.as_bytes(),
+            self.followee.as_bytes(),
+            &self.timestamp.to_be_bytes(),
+        ])
+    }
+
+    pub fn verify(&self, following: bool) -> bool {
+        self.follower != self.followee
+            && signing::verify_by_peer_id(&self.follower, &self.signature, &self.signing_bytes(following))
+    }
+}
+
+/// Longueur maximale (caractères) d'un nom affiché
+pub const MAX_PROFILE_NAME_CHARS: usize = 32;
+
+/// Longueur maximale (caractères) d'une bio
+pub const MAX_PROFILE_BIO_CHARS: usize = 280;
+
+/// Taille maximale (octets) d'une référence d'avatar
+pub const MAX_AVATAR_REF_BYTES: usize = 256;
+
+/// Profil public d'une identité : nom affiché, bio et avatar
+///
+/// Seul le profil le plus récent d'une identité compte. Les interfaces
+/// affichent ce nom plutôt que `Post::author_name`, que chaque post déclare
+/// librement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Profile {
+    /// PeerId de l'identité, qui contient sa clé publique
+    pub identity: String,
+    pub name: String,
+    #[serde(default)]
+    pub bio: String,
+    /// Référence de l'image (URL ou identifiant de contenu)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "typescript", ts(optional))]
+    pub avatar: Option<String>,
+    /// Date en millisecondes
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub timestamp: i64,
+    pub signature: String,
+}
+
+impl Profile {
+    /// Crée un profil signé par la clé privée de l'identité
+    pub fn new(name: String, bio: String, avatar: Option<String>, timestamp: i64, secret_key: &[u8; 32]) -> Self {
+        let mut profile = Self {
+            identity: signing::peer_id_from_secret_key(secret_key),
+            name,
+            bio,
+            avatar,
+            timestamp,
+            signature: String::new(),
+        };
+        profile.signature = signing::sign(secret_key, &profile.signing_bytes());
+        profile
+    }
+
+    /// Encodage canonique ; un avatar absent est encodé vide, ce qu'un avatar
+    /// renseigné ne peut pas être
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        signing::canonical_bytes("zeta2-profile-v1", &[
+            self.identity.as_bytes(),
+            self.name.as_bytes(),
+            self.bio.as_bytes(),
+            self.avatar.as_deref().unwrap_or_default().as_bytes(),
+            &self.timestamp.to_be_bytes(),
+        ])
+    }
+
+    /// Vérifie les limites de chaque champ et la signature de l'identité
+    pub fn verify(&self) -> bool {
+        let name_ok = !self.name.trim().is_empty()
+            && self.name.chars().count() <= MAX_PROFILE_NAME_CHARS
+            && !self.name.chars().any(char::is_control);
+        let bio_ok = self.bio.chars().count() <= MAX_PROFILE_BIO_CHARS
+            && !self.bio.chars().any(|c| c.is_control() && c != '\n');
+        let avatar_ok = self.avatar.as_deref().is_none_or(|avatar| {
+            !avatar.is_empty()
+                && avatar.len() <= MAX_AVATAR_REF_BYTES
+                && !avatar.chars().any(|c| c.is_whitespace() || c.is_control())
+        });
+        name_ok && bio_ok && avatar_ok
+            && signing::verify_by_peer_id(&self.identity, &self.signature, &self.signing_bytes())
+    }
+}
+
+/// Post accompagné de l'arbre de ses réponses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct ThreadNode {
+    pub post: Post,
+    /// Réponses directes, de la plus ancienne à la plus récente
+    pub replies: Vec<ThreadNode>,
+}
+
+impl ThreadNode {
+    /// Construit l'arbre d'un post, `replies` donnant les réponses directes d'un identifiant
+    pub fn build<'a>(post: &'a Post, replies: &dyn Fn(&str) -> Vec<&'a Post>) -> Self {
+        let mut visited = HashSet::new();
+        Self::build_from(post, replies, &mut visited)
+    }
+
+    fn build_from<'a>(post: &'a Post, replies: &dyn Fn(&str) -> Vec<&'a Post>, visited: &mut HashSet<&'a str>) -> Self {
+        visited.insert(&post.id);
+        let mut children = replies(&post.id);
+        children.sort_by_key(|p| p.timestamp);
+        // Des identifiants forgés peuvent former un cycle : chaque post n'apparaît qu'une fois
+        let mut nodes = Vec::with_capacity(children.len());
+        for child in children {
+            if !visited.contains(child.id.as_str()) {
+                nodes.push(Self::build_from(child, replies, visited));
+            }
+        }
+        Self { post: post.clone(), replies: nodes }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub address: String,
+    pub name: Option<String>,
+    pub is_browser: bool,
+    /// Clé publique ed25519 (hex) vérifiée pour ce peer
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+/// Messages publiés sur le topic gossipsub
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NetworkMessage {
+    Post(Post),
+    Heartbeat,
+    PeerJoined { peer_id: String, name: String },
+    PeerLeft { peer_id: String },
+    /// Annonce de l'URL WebSocket publique d'un relay
+    RelayAnnounce { peer_id: String, ws_url: String },
+    Reaction(Reaction),
+    Edit(PostEdit),
+    Delete(PostDelete),
+    Follow(Follow),
+    Unfollow(Follow),
+    /// Message chiffré pour un destinataire, relayé sans être lu
+    Direct(DirectMessage),
+    Profile(Profile),
+}
+
+impl NetworkMessage {
+    /// Type du message dans l'enveloppe (nom de la variante)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetworkMessage::Post(_) => "Post",
+            NetworkMessage::Heartbeat => "Heartbeat",
+            NetworkMessage::PeerJoined { .. } => "PeerJoined",
+            NetworkMessage::PeerLeft { .. } => "PeerLeft",
+            NetworkMessage::RelayAnnounce { .. } => "RelayAnnounce",
+            NetworkMessage::Reaction(_) => "Reaction",
+            NetworkMessage::Edit(_) => "Edit",
+            NetworkMessage::Delete(_) => "Delete",
+            NetworkMessage::Follow(_) => "Follow",
+            NetworkMessage::Unfollow(_) => "Unfollow",
+            NetworkMessage::Direct(_) => "Direct",
+            NetworkMessage::Profile(_) => "Profile",
+        }
+    }
+
+    /// Identité qui signe le message, `None` pour les messages non signés
+    pub fn signer(&self) -> Option<&str> {
+        match self {
+            NetworkMessage::Post(post) => Some(&post.author),
+            NetworkMessage::Reaction(reaction) => Some(&reaction.author),
+            NetworkMessage::Edit(edit) => Some(&edit.author),
+            NetworkMessage::Delete(delete) => Some(&delete.author),
+            NetworkMessage::Follow(follow) | NetworkMessage::Unfollow(follow) => Some(&follow.follower),
+            NetworkMessage::Direct(message) => Some(&message.sender),
+            NetworkMessage::Profile(profile) => Some(&profile.identity),
+            NetworkMessage::Heartbeat
+            | NetworkMessage::PeerJoined { .. }
+            | NetworkMessage::PeerLeft { .. }
+            | NetworkMessage::RelayAnnounce { .. } => None,
+        }
+    }
+
+    /// Indique si ce nœud sait interpréter un type de message
+    pub fn is_known_kind(kind: &str) -> bool {
+        matches!(
+            kind,
+            "Post"
+                | "Heartbeat"
+                | "PeerJoined"
+                | "PeerLeft"
+                | "RelayAnnounce"
+                | "Reaction"
+                | "Edit"
+                | "Delete"
+                | "Follow"
+                | "Unfollow"
+                | "Direct"
+                | "Profile"
+        )
+    }
+}
+
+/// Relay connu, tel qu'exposé aux navigateurs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct RelayInfo {
+    pub peer_id: String,
+    pub ws_url: String,
+    #[cfg_attr(feature = "typescript", ts(type = "number"))]
+    pub last_seen: i64,
+}
+
+/// Requête du protocole de synchronisation `/zeta2/sync/1.0.0`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SyncRequest {
+    /// Posts publiés depuis un timestamp (inclus)
+    Since { timestamp: i64, limit: usize },
+    /// Posts précis, par identifiant
+    Ids { ids: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub posts: Vec<Post>,
+    /// Profils des auteurs de ces posts
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+    /// Réactions à ces posts
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: [u8; 32] = [1; 32];
+    const MALLORY: [u8; 32] = [2; 32];
+
+    fn signed_post(secret_key: &[u8; 32]) -> Post {
+        let mut post = Post {
+            id: "p1".to_string(),
+            author: signing::peer_id_from_secret_key(secret_key),
+            author_name: "Alice".to_string(),
+            content: "Bonjour".to_string(),
+            timestamp: 1_700_000_000,
+            ..Default::default()
+        };
+        post.sign(secret_key);
+        post
+    }
+
+    fn attachment() -> Attachment {
+        Attachment { cid: "a".repeat(64), mime: "image/png".to_string(), size: 3, name: None }
+    }
+
+    #[test]
+    fn signer_is_the_signing_identity() {
+        let alice = signing::peer_id_from_secret_key(&ALICE);
+        let reaction = Reaction::new("p1".to_string(), "👍".to_string(), &ALICE);
+        let follow = Follow::new("12D3KooWautre".to_string(), true, 1, &ALICE);
+        assert_eq!(NetworkMessage::Follow(follow).signer(), Some(alice.as_str()));
+        assert_eq!(NetworkMessage::Reaction(reaction).signer(), Some(alice.as_str()));
+        assert_eq!(NetworkMessage::Heartbeat.signer(), None);
+    }
+
+    #[test]
+    fn signed_post_verifies() {
+        assert!(signed_post(&ALICE).verify());
+    }
+
+    #[test]
+    fn tampered_post_is_rejected() {
+        let mut post = signed_post(&ALICE);
+        post.content = "Au revoir".to_string();
+        assert!(!post.verify());
+
+        // Un autre auteur annoncé pour la même clé
+        let mut post = signed_post(&ALICE);
+        post.author = signing::peer_id_from_secret_key(&MALLORY);
+        assert!(!post.verify());
+
+        // La clé d'un autre avec la signature d'origine
+        let mut post = signed_post(&ALICE);
+        post.public_key = signing::public_key_hex(&MALLORY);
+        assert!(!post.verify());
+
+        // Auteur et clé remplacés ensemble : la signature ne correspond plus
+        let mut post = signed_post(&ALICE);
+        post.author = signing::peer_id_from_secret_key(&MALLORY);
+        post.public_key = signing::public_key_hex(&MALLORY);
+        assert!(!post.verify());
+    }
+
+    #[test]
+    fn legacy_post_signs_only_the_original_fields() {
+        let post = signed_post(&ALICE);
+        let timestamp = post.timestamp.to_be_bytes();
+        let expected = signing::canonical_bytes("zeta2-post-v1", &[
+            post.id.as_bytes(),
+            post.author.as_bytes(),
+            post.author_name.as_bytes(),
+            post.content.as_bytes(),
+            &timestamp,
+            post.public_key.as_bytes(),
+        ]);
+        assert_eq!(post.signing_bytes(), expected);
+    }
+
+    #[test]
+    fn reply_fields_are_appended_and_signed() {
+        let field = |bytes: &[u8]| signing::canonical_bytes("", &[bytes]);
+        let mut reply = signed_post(&ALICE);
+        reply.reply_to = Some("parent".to_string());
+        let expected = [signed_post(&ALICE).signing_bytes(), field(b"parent"), field(b"")].concat();
+        assert_eq!(reply.signing_bytes(), expected);
+
+        reply.sign(&ALICE);
+        assert!(reply.verify());
+        let mut moved = reply;
+        moved.reply_to = Some("autre".to_string());
+        assert!(!moved.verify());
+    }
+
+    #[test]
+    fn reaction_signature_covers_the_emoji() {
+        let reaction = Reaction::new("p1".to_string(), "👍".to_string(), &ALICE);
+        assert!(reaction.verify());
+        let mut changed = reaction.clone();
+        changed.emoji = "👎".to_string();
+        assert!(!changed.verify());
+        let mut moved = reaction;
+        moved.post_id = "p2".to_string();
+        assert!(!moved.verify());
+    }
+
+    #[test]
+    fn edit_must_come_from_the_author() {
+        let mut post = signed_post(&ALICE);
+        post.edit = Some(Box::new(PostEdit::new(post.id.clone(), "Modifié".to_string(), 1, &ALICE)));
+        assert!(post.verify());
+
+        post.edit = Some(Box::new(PostEdit::new(post.id.clone(), "Usurpé".to_string(), 1, &MALLORY)));
+        assert!(!post.verify());
+    }
+
+    #[test]
+    fn delete_signature_binds_the_author() {
+        let delete = PostDelete::new("p1".to_string(), 1, &ALICE);
+        assert!(delete.verify());
+        let mut forged = PostDelete::new("p1".to_string(), 1, &MALLORY);
+        forged.author = delete.author.clone();
+        assert!(!forged.verify());
+    }
+
+    #[test]
+    fn later_edit_supersedes_and_ties_break_on_signature() {
+        let first = PostEdit::new("p1".to_string(), "a".to_string(), 1_000, &ALICE);
+        let later = PostEdit::new("p1".to_string(), "b".to_string(), 1_001, &ALICE);
+        assert!(later.supersedes(&first));
+        assert!(!first.supersedes(&later));
+
+        let twin = PostEdit::new("p1".to_string(), "c".to_string(), 1_000, &ALICE);
+        assert_ne!(first.supersedes(&twin), twin.supersedes(&first));
+        assert!(!first.supersedes(&first));
+    }
+
+    #[test]
+    fn follow_is_not_an_unfollow() {
+        let follow = Follow::new(signing::peer_id_from_secret_key(&MALLORY), true, 1, &ALICE);
+        assert!(follow.verify(true));
+        // Un abonnement rejoué comme désabonnement
+        assert!(!follow.verify(false));
+        let unfollow = Follow::new(signing::peer_id_from_secret_key(&MALLORY), false, 1, &ALICE);
+        assert!(unfollow.verify(false));
+        assert!(!unfollow.verify(true));
+    }
+
+    #[test]
+    fn channel_is_appended_and_signed() {
+        let field = |bytes: &[u8]| signing::canonical_bytes("", &[bytes]);
+        let mut post = signed_post(&ALICE);
+        post.channel = Some("rust".to_string());
+        let expected = [signed_post(&ALICE).signing_bytes(), field(b""), field(b""), field(b"rust")].concat();
+        assert_eq!(post.signing_bytes(), expected);
+
+        post.sign(&ALICE);
+        assert!(post.verify());
+        let mut moved = post;
+        moved.channel = Some("musique".to_string());
+        assert!(!moved.verify());
+    }
+
+    #[test]
+    fn profile_is_signed_and_bounded() {
+        let profile = Profile::new("Alice".to_string(), String::new(), None, 1, &ALICE);
+        assert!(profile.verify());
+        let mut renamed = profile;
+        renamed.name = "Mallory".to_string();
+        assert!(!renamed.verify());
+
+        let long = Profile::new("a".repeat(MAX_PROFILE_NAME_CHARS + 1), String::new(), None, 1, &ALICE);
+        assert!(!long.verify());
+    }
+
+    #[test]
+    fn attachments_are_appended_and_signed() {
+        let field = |bytes: &[u8]| signing::canonical_bytes("", &[bytes]);
+        let mut post = signed_post(&ALICE);
+        post.attachments = vec![attachment()];
+        let expected = [
+            signed_post(&ALICE).signing_bytes(),
+            field(b""),
+            field(b""),
+            field(b""),
+            field(&attachment().signing_bytes()),
+        ]
+        .concat();
+        assert_eq!(post.signing_bytes(), expected);
+
+        post.sign(&ALICE);
+        assert!(post.verify());
+        let mut stripped = post;
+        stripped.attachments.clear();
+        assert!(!stripped.verify());
+    }
+}
@@ -0,0 +1,90 @@
+//! Signatures ed25519 des messages applicatifs
+//!
+//! Les champs signés sont encodés de façon canonique : un préfixe de domaine
+//! puis chaque champ précédé de sa longueur (u32 big-endian). Le nœud natif et
+//! le nœud WASM partagent ce code, un post se vérifie donc de la même façon
+//! quelle que soit son origine.
+
+use ed25519_dalek::{Signer, Verifier};
+
+/// Encode les champs de manière non ambiguë pour la signature
+pub fn canonical_bytes(domain: &str, fields: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(domain.len() + fields.iter().map(|f| f.len() + 4).sum::<usize>());
+    out.extend_from_slice(domain.as_bytes());
+    for field in fields {
+        out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        out.extend_from_slice(field);
+    }
+    out
+}
+
+/// Clé publique ed25519 (hex) associée à une clé privée
+pub fn public_key_hex(secret_key: &[u8; 32]) -> String {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(secret_key);
+    hex::encode(signing_key.verifying_key().as_bytes())
+}
+
+/// Signe les octets et retourne la signature encodée en hex
+pub fn sign(secret_key: &[u8; 32], bytes: &[u8]) -> String {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(secret_key);
+    hex::encode(signing_key.sign(bytes).to_bytes())
+}
+
+/// Vérifie une signature hex avec une clé publique ed25519 hex
+pub fn verify(public_key_hex: &str, signature_hex: &str, bytes: &[u8]) -> bool {
+    let Some(public_key) = decode_public_key(public_key_hex) else {
+        return false;
+    };
+    let Ok(sig_bytes) = hex::decode(signature_hex) else {
+        return false;
+    };
+    let Ok(signature) = ed25519_dalek::Signature::from_slice(&sig_bytes) else {
+        return false;
+    };
+    public_key.verify(bytes, &signature).is_ok()
+}
+
+/// Calcule le PeerId libp2p d'une clé publique ed25519
+///
+/// La clé est encodée en protobuf (`KeyType::Ed25519`), puis enveloppée dans
+/// un multihash identité (la clé fait moins de 42 octets) et encodée en base58,
+/// exactement comme `PeerId::from_public_key` de libp2p.
+pub fn peer_id_from_public_key(public_key: &[u8; 32]) -> String {
+    let mut protobuf = vec![0x08, 0x01, 0x12, 0x20];
+    protobuf.extend_from_slice(public_key);
+    let mut multihash = vec![0x00, protobuf.len() as u8];
+    multihash.extend_from_slice(&protobuf);
+    bs58::encode(multihash).into_string()
+}
+
+/// PeerId libp2p dérivé d'une clé publique ed25519 hex
+pub fn peer_id_from_public_key_hex(public_key_hex: &str) -> Option<String> {
+    let public_key = decode_public_key(public_key_hex)?;
+    Some(peer_id_from_public_key(public_key.as_bytes()))
+}
+
+/// Clé publique ed25519 (hex) contenue dans un PeerId
+///
+/// Un PeerId ed25519 est un multihash identité : la clé s'y lit directement.
+pub fn public_key_hex_from_peer_id(peer_id: &str) -> Option<String> {
+    let bytes = bs58::decode(peer_id).into_vec().ok()?;
+    let key = bytes.strip_prefix(&[0x00, 0x24, 0x08, 0x01, 0x12, 0x20])?;
+    (key.len() == 32).then(|| hex::encode(key))
+}
+
+/// PeerId libp2p correspondant à une clé privée ed25519
+pub fn peer_id_from_secret_key(secret_key: &[u8; 32]) -> String {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(secret_key);
+    peer_id_from_public_key(signing_key.verifying_key().as_bytes())
+}
+
+/// Vérifie une signature avec la clé contenue dans le PeerId de l'auteur
+pub fn verify_by_peer_id(peer_id: &str, signature_hex: &str, bytes: &[u8]) -> bool {
+    public_key_hex_from_peer_id(peer_id).is_some_and(|public_key| verify(&public_key, signature_hex, bytes))
+}
+
+fn decode_public_key(public_key_hex: &str) -> Option<ed25519_dalek::VerifyingKey> {
+    let bytes = hex::decode(public_key_hex).ok()?;
+    let bytes = <[u8; 32]>::try_from(bytes.as_slice()).ok()?;
+    ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok()
+}
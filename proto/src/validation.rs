@@ -0,0 +1,254 @@
+//! Validation des messages gossip avant qu'ils soient relayés
+//!
+//! Gossipsub ne relaie un message qu'une fois validé par l'application. Les
+//! contrôles sont sans état : forme du message, longueur du texte, date
+//! plausible puis signature, la plus coûteuse en dernier. Un message d'un
+//! type inconnu n'est pas contrôlé : il vient peut-être d'une version plus
+//! récente du protocole et doit continuer de circuler. Seule sa taille est
+//! bornée.
+
+use crate::envelope::{self, Decoded};
+use crate::{grapheme_count, NetworkMessage, MAX_POST_GRAPHEMES};
+use std::fmt;
+
+/// Avance maximale (secondes) d'une date sur l'horloge locale
+pub const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// Âge maximal (secondes) d'un message : l'historique passe par la
+/// synchronisation, pas par gossipsub
+pub const MAX_MESSAGE_AGE_SECS: i64 = 24 * 3600;
+
+/// Taille maximale (octets) d'une enveloppe d'un type inconnu, relayée sans être contrôlée
+pub const MAX_UNKNOWN_ENVELOPE_BYTES: usize = 16 * 1024;
+
+/// Motif du rejet d'un message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rejection {
+    /// Enveloppe ou contenu illisible
+    Malformed(String),
+    /// Texte de plus de `MAX_POST_GRAPHEMES` graphèmes
+    TooLong(usize),
+    /// Enveloppe d'un type inconnu de plus de `MAX_UNKNOWN_ENVELOPE_BYTES` octets
+    TooLarge(usize),
+    /// Date hors de la fenêtre acceptée, écart en secondes avec l'horloge locale
+    Timestamp(i64),
+    /// Signature invalide ou champs hors limites
+    Signature,
+    /// Annonce de relay publiée par un autre pair que le relay
+    Spoofed,
+}
+
+impl Rejection {
+    /// Le message est illisible, plutôt qu'invalide
+    pub fn is_malformed(&self) -> bool {
+        matches!(self, Rejection::Malformed(_))
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::Malformed(e) => write!(f, "{}", e),
+            Rejection::TooLong(len) => write!(f, "texte de {} graphèmes (max {})", len, MAX_POST_GRAPHEMES),
+            Rejection::TooLarge(len) => write!(f, "message inconnu de {} octets (max {})", len, MAX_UNKNOWN_ENVELOPE_BYTES),
+            Rejection::Timestamp(skew) => write!(f, "date décalée de {} s", skew),
+            Rejection::Signature => write!(f, "signature invalide"),
+            Rejection::Spoofed => write!(f, "annonce de relay usurpée"),
+        }
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+/// Décode puis contrôle un message reçu du topic
+///
+/// `source` est l'auteur gossipsub du message, `now` l'heure locale en secondes.
+pub fn validate(bytes: &[u8], source: Option<&str>, now: i64) -> Result<Decoded, Rejection> {
+    let decoded = envelope::decode(bytes).map_err(|e| Rejection::Malformed(e.to_string()))?;
+    match &decoded {
+        Decoded::Message(msg) => check(msg, source, now)?,
+        Decoded::Unknown { .. } if bytes.len() > MAX_UNKNOWN_ENVELOPE_BYTES => {
+            return Err(Rejection::TooLarge(bytes.len()));
+        }
+        Decoded::Unknown { .. } => {}
+    }
+    Ok(decoded)
+}
+
+/// Contrôle un message déjà décodé
+pub fn check(msg: &NetworkMessage, source: Option<&str>, now: i64) -> Result<(), Rejection> {
+    let content = match msg {
+        NetworkMessage::Post(post) => Some(post.current_content()),
+        NetworkMessage::Edit(edit) => Some(edit.content.as_str()),
+        _ => None,
+    };
+    if let Some(len) = content.map(grapheme_count).filter(|len| *len > MAX_POST_GRAPHEMES) {
+        return Err(Rejection::TooLong(len));
+    }
+
+    // Posts, suppressions et messages directs sont datés en secondes,
+    // modifications, abonnements et profils en millisecondes
+    let timestamp = match msg {
+        NetworkMessage::Post(post) => Some(post.timestamp),
+        NetworkMessage::Edit(edit) => Some(edit.edited_at / 1000),
+        NetworkMessage::Delete(delete) => Some(delete.deleted_at),
+        NetworkMessage::Direct(message) => Some(message.timestamp),
+        NetworkMessage::Follow(follow) | NetworkMessage::Unfollow(follow) => Some(follow.timestamp / 1000),
+        NetworkMessage::Profile(profile) => Some(profile.timestamp / 1000),
+        _ => None,
+    };
+    if let Some(timestamp) = timestamp {
+        let skew = timestamp.saturating_sub(now);
+        if !(-MAX_MESSAGE_AGE_SECS..=MAX_CLOCK_SKEW_SECS).contains(&skew) {
+            return Err(Rejection::Timestamp(skew));
+        }
+    }
+
+    let signed = match msg {
+        NetworkMessage::Post(post) => post.verify(),
+        NetworkMessage::Reaction(reaction) => reaction.verify(),
+        NetworkMessage::Edit(edit) => edit.verify(),
+        NetworkMessage::Delete(delete) => delete.verify(),
+        NetworkMessage::Follow(follow) => follow.verify(true),
+        NetworkMessage::Unfollow(follow) => follow.verify(false),
+        NetworkMessage::Direct(message) => message.verify(),
+        NetworkMessage::Profile(profile) => profile.verify(),
+        NetworkMessage::RelayAnnounce { peer_id, .. } => {
+            // L'annonce doit être publiée (et signée gossipsub) par le relay lui-même
+            if source != Some(peer_id.as_str()) {
+                return Err(Rejection::Spoofed);
+            }
+            true
+        }
+        NetworkMessage::Heartbeat | NetworkMessage::PeerJoined { .. } | NetworkMessage::PeerLeft { .. } => true,
+    };
+    if !signed {
+        return Err(Rejection::Signature);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{signing, DirectMessage, Follow, Post, PostEdit, Profile, Reaction};
+
+    const ALICE: [u8; 32] = [1; 32];
+    const NOW: i64 = 1_700_000_000;
+
+    fn post(content: &str, timestamp: i64) -> Post {
+        let mut post = Post {
+            id: "p1".to_string(),
+            author: signing::peer_id_from_secret_key(&ALICE),
+            author_name: "Alice".to_string(),
+            content: content.to_string(),
+            timestamp,
+            ..Default::default()
+        };
+        post.sign(&ALICE);
+        post
+    }
+
+    fn validate_msg(msg: &NetworkMessage, source: Option<&str>) -> Result<(), Rejection> {
+        validate(&envelope::encode(msg), source, NOW).map(|_| ())
+    }
+
+    #[test]
+    fn valid_messages_are_accepted() {
+        let alice = signing::peer_id_from_secret_key(&ALICE);
+        let bob = signing::peer_id_from_secret_key(&[2; 32]);
+        let messages = [
+            NetworkMessage::Post(post("Bonjour", NOW)),
+            NetworkMessage::Reaction(Reaction::new("p1".to_string(), "👍".to_string(), &ALICE)),
+            NetworkMessage::Edit(PostEdit::new("p1".to_string(), "Modifié".to_string(), NOW * 1000, &ALICE)),
+            NetworkMessage::Delete(crate::PostDelete::new("p1".to_string(), NOW, &ALICE)),
+            NetworkMessage::Follow(Follow::new(bob.clone(), true, NOW * 1000, &ALICE)),
+            NetworkMessage::Unfollow(Follow::new(bob.clone(), false, NOW * 1000, &ALICE)),
+            NetworkMessage::Direct(DirectMessage::seal(&ALICE, bob, "Salut", NOW).unwrap()),
+            NetworkMessage::Profile(Profile::new("Alice".to_string(), String::new(), None, NOW * 1000, &ALICE)),
+            NetworkMessage::RelayAnnounce { peer_id: alice.clone(), ws_url: "wss://relay".to_string() },
+            NetworkMessage::Heartbeat,
+        ];
+        for msg in &messages {
+            assert_eq!(validate_msg(msg, Some(&alice)), Ok(()), "{}", msg.kind());
+        }
+    }
+
+    #[test]
+    fn malformed_bytes_are_rejected() {
+        let rejection = validate(b"{", None, NOW).unwrap_err();
+        assert!(rejection.is_malformed());
+    }
+
+    #[test]
+    fn unknown_kind_is_not_checked() {
+        let bytes = br#"{"version":1,"kind":"Poll","payload":null}"#;
+        assert!(matches!(validate(bytes, None, NOW), Ok(Decoded::Unknown { .. })));
+    }
+
+    #[test]
+    fn large_unknown_envelope_is_rejected() {
+        let envelope = |len| format!(r#"{{"version":2,"kind":"Post","payload":"{}"}}"#, "a".repeat(len));
+        let overhead = envelope(0).len();
+        let fits = envelope(MAX_UNKNOWN_ENVELOPE_BYTES - overhead);
+        assert!(matches!(validate(fits.as_bytes(), None, NOW), Ok(Decoded::Unknown { .. })));
+        let large = envelope(MAX_UNKNOWN_ENVELOPE_BYTES - overhead + 1);
+        assert_eq!(validate(large.as_bytes(), None, NOW).unwrap_err(), Rejection::TooLarge(MAX_UNKNOWN_ENVELOPE_BYTES + 1));
+    }
+
+    #[test]
+    fn long_text_is_rejected() {
+        let long = "a".repeat(MAX_POST_GRAPHEMES + 1);
+        let msg = NetworkMessage::Post(post(&long, NOW));
+        assert_eq!(validate_msg(&msg, None), Err(Rejection::TooLong(MAX_POST_GRAPHEMES + 1)));
+
+        let edit = PostEdit::new("p1".to_string(), long, NOW * 1000, &ALICE);
+        assert_eq!(validate_msg(&NetworkMessage::Edit(edit), None), Err(Rejection::TooLong(MAX_POST_GRAPHEMES + 1)));
+
+        // Un emoji composé compte pour un graphème
+        let family = "👨‍👩‍👧".repeat(MAX_POST_GRAPHEMES);
+        assert_eq!(validate_msg(&NetworkMessage::Post(post(&family, NOW)), None), Ok(()));
+    }
+
+    #[test]
+    fn timestamp_window_is_enforced() {
+        let at = |timestamp| validate_msg(&NetworkMessage::Post(post("Bonjour", timestamp)), None);
+        assert_eq!(at(NOW + MAX_CLOCK_SKEW_SECS), Ok(()));
+        assert_eq!(at(NOW + MAX_CLOCK_SKEW_SECS + 1), Err(Rejection::Timestamp(MAX_CLOCK_SKEW_SECS + 1)));
+        assert_eq!(at(NOW - MAX_MESSAGE_AGE_SECS), Ok(()));
+        assert_eq!(at(NOW - MAX_MESSAGE_AGE_SECS - 1), Err(Rejection::Timestamp(-MAX_MESSAGE_AGE_SECS - 1)));
+
+        // Modifications, abonnements et profils sont datés en millisecondes
+        let edit = PostEdit::new("p1".to_string(), "Modifié".to_string(), (NOW + MAX_CLOCK_SKEW_SECS + 1) * 1000, &ALICE);
+        assert!(matches!(validate_msg(&NetworkMessage::Edit(edit), None), Err(Rejection::Timestamp(_))));
+        let bob = signing::peer_id_from_secret_key(&[2; 32]);
+        let follow = Follow::new(bob, true, (NOW + MAX_CLOCK_SKEW_SECS + 1) * 1000, &ALICE);
+        assert!(matches!(validate_msg(&NetworkMessage::Follow(follow), None), Err(Rejection::Timestamp(_))));
+        let profile = Profile::new("Alice".to_string(), String::new(), None, NOW, &ALICE);
+        assert!(matches!(validate_msg(&NetworkMessage::Profile(profile), None), Err(Rejection::Timestamp(_))));
+    }
+
+    #[test]
+    fn bad_signature_is_rejected() {
+        let mut tampered = post("Bonjour", NOW);
+        tampered.content = "Au revoir".to_string();
+        assert_eq!(validate_msg(&NetworkMessage::Post(tampered), None), Err(Rejection::Signature));
+
+        let mut reaction = Reaction::new("p1".to_string(), "👍".to_string(), &ALICE);
+        reaction.emoji = "👎".to_string();
+        assert_eq!(validate_msg(&NetworkMessage::Reaction(reaction), None), Err(Rejection::Signature));
+
+        // Un abonnement rejoué comme désabonnement
+        let bob = signing::peer_id_from_secret_key(&[2; 32]);
+        let follow = Follow::new(bob, true, NOW * 1000, &ALICE);
+        assert_eq!(validate_msg(&NetworkMessage::Unfollow(follow), None), Err(Rejection::Signature));
+    }
+
+    #[test]
+    fn relay_announce_must_come_from_the_relay() {
+        let alice = signing::peer_id_from_secret_key(&ALICE);
+        let msg = NetworkMessage::RelayAnnounce { peer_id: alice, ws_url: "wss://relay".to_string() };
+        assert_eq!(validate_msg(&msg, Some("12D3KooWautre")), Err(Rejection::Spoofed));
+        assert_eq!(validate_msg(&msg, None), Err(Rejection::Spoofed));
+    }
+}
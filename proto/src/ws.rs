@@ -0,0 +1,249 @@
+//! Messages WebSocket entre un relay et ses clients navigateur
+//!
+//! Les typings TypeScript de `static/zeta_protocol.d.ts` sont générés à partir
+//! de ces types : `cargo run -p zeta-proto --features typescript --bin ws-typings`.
+
+use crate::{signing, Attachment, DirectMessage, Follow, PeerInfo, Post, PostDelete, PostEdit, Profile, Reaction, ReactionCounts, RelayInfo, ThreadNode};
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// Message envoyé par le relay au client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame {
+    /// État initial, avec le défi d'authentification à signer
+    Init {
+        peer_id: String,
+        nonce: String,
+        peers: Vec<PeerInfo>,
+        posts: Vec<Post>,
+        #[serde(default)]
+        relays: Vec<RelayInfo>,
+        /// Réactions par post
+        #[serde(default)]
+        reactions: HashMap<String, ReactionCounts>,
+        /// Profils vérifiés connus du relay
+        #[serde(default)]
+        profiles: Vec<Profile>,
+    },
+    NewPost { post: Post },
+    /// Nouveaux posts reçus d'une même synchronisation, du plus récent au plus
+    /// ancien, avec les compteurs de réactions qu'elle a modifiés
+    NewPosts {
+        posts: Vec<Post>,
+        #[serde(default)]
+        reactions: HashMap<String, ReactionCounts>,
+    },
+    /// Post modifié par son auteur (`post.edit` renseigné)
+    PostUpdated { post: Post },
+    /// Post supprimé par son auteur
+    PostDeleted { post_id: String },
+    /// Nouveaux compteurs de réactions d'un post
+    Reactions { post_id: String, counts: ReactionCounts },
+    PeerJoined { peer_id: String },
+    PeerLeft { peer_id: String },
+    /// Le client a prouvé la possession de son PeerId
+    Identified { peer_id: String },
+    Pong,
+    /// Réponse à `get_thread`
+    Thread { thread: ThreadNode },
+    /// Auteurs suivis par une identité, envoyé à chaque changement
+    Following { identity: String, following: Vec<String> },
+    /// Réponse à `subscribe` : posts déjà connus du fil choisi
+    Feed { mode: FeedMode, posts: Vec<Post> },
+    /// Canaux reçus sur la connexion, envoyé à chaque changement
+    Channels { channels: Vec<String> },
+    /// Réponse à `join_channel` : posts déjà connus du canal
+    Channel { channel: String, posts: Vec<Post> },
+    /// Message direct chiffré envoyé ou reçu par le client authentifié
+    Direct { message: DirectMessage },
+    /// Profil nouveau ou mis à jour
+    Profile { profile: Profile },
+    /// Message du client refusé
+    Error { code: ErrorCode, message: String },
+    /// Publication refusée : limite de débit atteinte, réessayer après le délai
+    RateLimited {
+        scope: LimitScope,
+        #[cfg_attr(feature = "typescript", ts(type = "number"))]
+        retry_after_ms: u64,
+    },
+}
+
+impl ServerFrame {
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        ServerFrame::Error { code, message: message.into() }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("ServerFrame sérialisable")
+    }
+}
+
+/// Limite de débit atteinte, transmise dans la trame `rate_limited`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum LimitScope {
+    /// Connexion WebSocket
+    Connection,
+    /// Adresse IP du client
+    Ip,
+    /// Identité qui signe les messages
+    Identity,
+    /// Identité signataire d'un message gossip, ou pair qui l'a transmis s'il
+    /// n'est pas signé ou d'un type inconnu
+    Peer,
+}
+
+impl LimitScope {
+    pub const ALL: [LimitScope; 4] = [LimitScope::Connection, LimitScope::Ip, LimitScope::Identity, LimitScope::Peer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitScope::Connection => "connection",
+            LimitScope::Ip => "ip",
+            LimitScope::Identity => "identity",
+            LimitScope::Peer => "peer",
+        }
+    }
+}
+
+/// Raison d'un refus, transmise dans la trame `error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// JSON illisible ou type de message inconnu
+    InvalidFrame,
+    /// Post signé reçu avant `identify`, ou pour une autre identité
+    Unauthenticated,
+    /// Post refusé : signature, longueur ou date invalide
+    InvalidPost,
+    /// Post déjà connu
+    DuplicatePost,
+    /// Preuve d'identité invalide
+    InvalidIdentity,
+    /// `identify` reçu alors que le client est déjà authentifié
+    AlreadyIdentified,
+    /// Post demandé inconnu du relay
+    UnknownPost,
+    /// Réaction mal formée ou à signature invalide
+    InvalidReaction,
+    /// Modification ou suppression refusée (signature, auteur ou post inconnu)
+    InvalidEdit,
+    /// Abonnement ou désabonnement à signature invalide
+    InvalidFollow,
+    /// Message direct mal formé ou à signature invalide
+    InvalidDirect,
+    /// Nom de canal invalide
+    InvalidChannel,
+    /// Trop de canaux suivis par la connexion ou par l'ensemble des navigateurs
+    TooManyChannels,
+    /// Pièce jointe inconnue du relay ou trop nombreuses
+    InvalidAttachment,
+    /// Profil mal formé, à signature invalide ou d'une autre identité
+    InvalidProfile,
+    /// Post non signé d'un client authentifié ou d'un autre que l'opérateur du relay
+    SignatureRequired,
+}
+
+/// Fil reçu par un client WebSocket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(rename_all = "snake_case")]
+pub enum FeedMode {
+    /// Tous les posts du réseau
+    #[default]
+    Global,
+    /// Uniquement les posts des auteurs suivis (et les siens)
+    Following,
+}
+
+/// Message envoyé par le client au relay
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientFrame {
+    Post(PostPayload),
+    /// Réponse au défi d'authentification envoyé dans `init`
+    Identify {
+        peer_id: String,
+        public_key: String,
+        signature: String,
+    },
+    /// Demande un post et l'arbre de ses réponses
+    GetThread { post_id: String },
+    /// Réaction signée par le client authentifié
+    React { reaction: Reaction },
+    /// Modification d'un post du client authentifié
+    Edit { edit: PostEdit },
+    /// Suppression d'un post du client authentifié
+    Delete { delete: PostDelete },
+    /// Abonnement signé par le client authentifié
+    Follow { follow: Follow },
+    /// Désabonnement signé par le client authentifié
+    Unfollow { follow: Follow },
+    /// Message direct chiffré par le client authentifié
+    Direct { message: DirectMessage },
+    /// Profil signé par le client authentifié
+    SetProfile { profile: Profile },
+    /// Choisit le fil diffusé sur la connexion (`global` par défaut)
+    Subscribe { mode: FeedMode },
+    /// Reçoit aussi les posts d'un canal
+    JoinChannel { channel: String },
+    /// Ne reçoit plus les posts d'un canal
+    LeaveChannel { channel: String },
+    Ping,
+}
+
+/// Contenu d'une trame `post`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(untagged)]
+pub enum PostPayload {
+    /// Post déjà signé par le client (nœud WASM)
+    Signed { post: Post },
+    /// Contenu brut, signé par le relay pour son opérateur
+    Unsigned {
+        content: String,
+        author_name: String,
+        #[serde(default)]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        reply_to: Option<String>,
+        /// Canal du post, celui du parent pour une réponse
+        #[serde(default)]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        channel: Option<String>,
+        /// Fichiers déjà envoyés au relay (`POST /api/blobs`)
+        #[serde(default)]
+        #[cfg_attr(feature = "typescript", ts(as = "Option<Vec<Attachment>>", optional))]
+        attachments: Vec<Attachment>,
+    },
+}
+
+impl ClientFrame {
+    /// La trame publie un message sur le réseau et compte donc dans les limites de débit
+    pub fn publishes(&self) -> bool {
+        matches!(
+            self,
+            ClientFrame::Post(_)
+                | ClientFrame::React { .. }
+                | ClientFrame::Edit { .. }
+                | ClientFrame::Delete { .. }
+                | ClientFrame::Follow { .. }
+                | ClientFrame::Unfollow { .. }
+                | ClientFrame::Direct { .. }
+                | ClientFrame::SetProfile { .. }
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("ClientFrame sérialisable")
+    }
+}
+
+/// Octets signés par le client pour répondre au défi `nonce`
+pub fn identify_bytes(nonce: &str, peer_id: &str) -> Vec<u8> {
+    signing::canonical_bytes("zeta2-identify-v1", &[nonce.as_bytes(), peer_id.as_bytes()])
+}
@@ -0,0 +1,154 @@
+//! Stockage des fichiers joints aux posts
+//!
+//! Chaque fichier est rangé sous son CID dans `blobs_dir` (ou en mémoire avec
+//! `--in-memory`). Le volume total est borné par un quota : une fois plein, le
+//! relay refuse les nouveaux fichiers plutôt que d'effacer ceux qu'il sert.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tracing::warn;
+use zeta_proto::blob::{self, Attachment};
+
+#[derive(Debug)]
+pub enum BlobError {
+    /// Fichier plus gros que `MAX_BLOB_BYTES`
+    TooLarge,
+    /// Type de contenu refusé
+    Unsupported,
+    /// Quota du relay atteint
+    QuotaExceeded,
+    Io(io::Error),
+}
+
+impl fmt::Display for BlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobError::TooLarge => write!(f, "fichier de plus de {} octets", blob::MAX_BLOB_BYTES),
+            BlobError::Unsupported => write!(f, "type de fichier non accepté"),
+            BlobError::QuotaExceeded => write!(f, "quota de stockage atteint"),
+            BlobError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for BlobError {
+    fn from(e: io::Error) -> Self {
+        BlobError::Io(e)
+    }
+}
+
+#[derive(Default)]
+struct BlobIndex {
+    /// CID -> taille
+    sizes: HashMap<String, u64>,
+    used: u64,
+    /// Contenus, seulement sans dossier de stockage
+    memory: HashMap<String, Vec<u8>>,
+}
+
+pub struct BlobStore {
+    /// Dossier des fichiers, `None` pour garder les fichiers en mémoire
+    dir: Option<PathBuf>,
+    /// Volume maximal (octets)
+    quota: u64,
+    index: Mutex<BlobIndex>,
+}
+
+impl BlobStore {
+    /// Ouvre le dossier de stockage et recense les fichiers déjà présents
+    pub fn open(dir: impl Into<PathBuf>, quota: u64) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let mut index = BlobIndex::default();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !blob::is_valid_cid(&name) {
+                continue;
+            }
+            let size = entry.metadata()?.len();
+            index.used += size;
+            index.sizes.insert(name, size);
+        }
+        Ok(Self { dir: Some(dir), quota, index: Mutex::new(index) })
+    }
+
+    pub fn in_memory(quota: u64) -> Self {
+        Self { dir: None, quota, index: Mutex::new(BlobIndex::default()) }
+    }
+
+    /// Range un fichier et retourne sa description ; un fichier déjà connu
+    /// ne compte pas deux fois dans le quota
+    pub fn put(&self, bytes: &[u8], name: Option<String>) -> Result<Attachment, BlobError> {
+        if bytes.len() > blob::MAX_BLOB_BYTES {
+            return Err(BlobError::TooLarge);
+        }
+        let attachment = Attachment::describe(bytes, name).ok_or(BlobError::Unsupported)?;
+
+        let mut index = self.index.lock().unwrap();
+        if index.sizes.contains_key(&attachment.cid) {
+            return Ok(attachment);
+        }
+        if index.used + attachment.size > self.quota {
+            return Err(BlobError::QuotaExceeded);
+        }
+        match &self.dir {
+            Some(dir) => {
+                // Écrire dans un fichier temporaire puis renommer : un CID présent est toujours complet
+                let tmp_path = dir.join(format!("{}.tmp", attachment.cid));
+                fs::write(&tmp_path, bytes)?;
+                fs::rename(&tmp_path, dir.join(&attachment.cid))?;
+            }
+            None => {
+                index.memory.insert(attachment.cid.clone(), bytes.to_vec());
+            }
+        }
+        index.used += attachment.size;
+        index.sizes.insert(attachment.cid.clone(), attachment.size);
+        Ok(attachment)
+    }
+
+    /// Contenu d'un fichier et son type MIME
+    pub fn get(&self, cid: &str) -> Option<(Vec<u8>, &'static str)> {
+        let index = self.index.lock().unwrap();
+        if !index.sizes.contains_key(cid) {
+            return None;
+        }
+        let bytes = match &self.dir {
+            Some(dir) => match fs::read(dir.join(cid)) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!("⚠️  Fichier {} illisible: {}", cid, e);
+                    return None;
+                }
+            },
+            None => index.memory.get(cid)?.clone(),
+        };
+        let mime = blob::sniff_mime(&bytes)?;
+        Some((bytes, mime))
+    }
+
+    pub fn contains(&self, cid: &str) -> bool {
+        self.index.lock().unwrap().sizes.contains_key(cid)
+    }
+
+    /// Description d'un fichier stocké, avec le nom choisi par le client
+    pub fn attachment(&self, cid: &str, name: Option<String>) -> Option<Attachment> {
+        let (bytes, _) = self.get(cid)?;
+        Attachment::describe(&bytes, name)
+    }
+
+    /// Occupation du stockage
+    pub fn snapshot(&self) -> serde_json::Value {
+        let index = self.index.lock().unwrap();
+        serde_json::json!({
+            "count": index.sizes.len(),
+            "used_bytes": index.used,
+            "quota_bytes": self.quota,
+        })
+    }
+}
@@ -0,0 +1,25 @@
+//! Protocole d'échange de blocs (`/zeta2/blocks/1.0.0`)
+//!
+//! Les posts ne référencent leurs fichiers que par CID. Quand un nœud reçoit
+//! un post dont il n'a pas les fichiers, il les demande à quelques pairs
+//! connectés ; le premier contenu dont l'empreinte correspond au CID est
+//! gardé, les réponses suivantes sont ignorées.
+
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::StreamProtocol;
+use std::time::Duration;
+use zeta_proto::blob::{BlockRequest, BlockResponse};
+
+pub const BLOCKS_PROTOCOL: StreamProtocol = StreamProtocol::new("/zeta2/blocks/1.0.0");
+
+/// Nombre de pairs interrogés pour chaque bloc manquant
+pub const BLOCK_FANOUT: usize = 3;
+
+pub type Behaviour = request_response::json::Behaviour<BlockRequest, BlockResponse>;
+
+pub fn new_behaviour() -> Behaviour {
+    request_response::json::Behaviour::new(
+        [(BLOCKS_PROTOCOL, ProtocolSupport::Full)],
+        request_response::Config::default().with_request_timeout(Duration::from_secs(60)),
+    )
+}
@@ -0,0 +1,123 @@
+//! Canaux suivis par le nœud et par ses navigateurs
+//!
+//! Le nœud s'abonne au topic gossipsub d'un canal tant que lui-même ou au
+//! moins une connexion WebSocket le suit. Chaque changement réveille la
+//! boucle du swarm, qui aligne ses abonnements gossipsub sur `wanted` : le
+//! départ du dernier navigateur d'un canal l'en désabonne.
+//!
+//! Les navigateurs ne peuvent pas abonner le nœud à un nombre illimité de
+//! topics : chaque connexion suit au plus `MAX_CHANNELS_PER_CONNECTION`
+//! canaux, et l'ensemble des connexions au plus `MAX_BROWSER_CHANNELS`.
+
+use std::collections::{BTreeSet, HashMap};
+use zeta_proto::channel::DEFAULT_CHANNEL;
+
+/// Canaux suivis par une connexion WebSocket, canal par défaut compris
+pub const MAX_CHANNELS_PER_CONNECTION: usize = 16;
+
+/// Canaux distincts demandés par l'ensemble des navigateurs
+pub const MAX_BROWSER_CHANNELS: usize = 256;
+
+#[derive(Default)]
+pub struct Channels {
+    /// Canaux suivis par le nœud lui-même (configuration, REST)
+    local: BTreeSet<String>,
+    /// Canaux demandés par les navigateurs : canal -> nombre de connexions
+    browsers: HashMap<String, usize>,
+}
+
+impl Channels {
+    pub fn new(local: impl IntoIterator<Item = String>) -> Self {
+        Self { local: local.into_iter().collect(), browsers: HashMap::new() }
+    }
+
+    /// Suit un canal pour le nœud, retourne `false` s'il l'était déjà
+    pub fn join(&mut self, channel: &str) -> bool {
+        self.local.insert(channel.to_string())
+    }
+
+    /// Ne suit plus un canal pour le nœud, retourne `false` s'il ne l'était pas
+    pub fn leave(&mut self, channel: &str) -> bool {
+        self.local.remove(channel)
+    }
+
+    /// Une connexion WebSocket suit un canal, retourne `false` si les
+    /// navigateurs demandent déjà `MAX_BROWSER_CHANNELS` autres canaux. Les
+    /// canaux suivis par le nœud, qui n'ajoutent pas de topic, sont exemptés.
+    pub fn add_browser(&mut self, channel: &str) -> bool {
+        let new_topic = !self.browsers.contains_key(channel) && channel != DEFAULT_CHANNEL && !self.local.contains(channel);
+        if new_topic && self.browsers.len() >= MAX_BROWSER_CHANNELS {
+            return false;
+        }
+        *self.browsers.entry(channel.to_string()).or_default() += 1;
+        true
+    }
+
+    /// Une connexion WebSocket ne suit plus un canal (ou s'est fermée),
+    /// retourne `true` si c'était la dernière
+    pub fn remove_browser(&mut self, channel: &str) -> bool {
+        let Some(count) = self.browsers.get_mut(channel) else {
+            return false;
+        };
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        self.browsers.remove(channel);
+        true
+    }
+
+    /// Canaux suivis par le nœud lui-même, canal par défaut compris
+    pub fn local(&self) -> Vec<String> {
+        let mut channels = self.local.clone();
+        channels.insert(DEFAULT_CHANNEL.to_string());
+        channels.into_iter().collect()
+    }
+
+    /// Canaux dont le nœud doit recevoir les posts
+    pub fn wanted(&self) -> BTreeSet<String> {
+        let mut channels: BTreeSet<String> = self.browsers.keys().cloned().collect();
+        channels.extend(self.local());
+        channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_browser_leaving_drops_the_channel() {
+        let mut channels = Channels::new(["rust".to_string()]);
+        assert!(channels.add_browser("cuisine"));
+        assert!(channels.add_browser("cuisine"));
+        assert!(channels.add_browser("rust"));
+
+        assert!(!channels.remove_browser("cuisine"));
+        assert!(channels.wanted().contains("cuisine"));
+        assert!(channels.remove_browser("cuisine"));
+        assert!(!channels.wanted().contains("cuisine"));
+
+        // Un canal suivi par le nœud reste voulu sans navigateur
+        assert!(channels.remove_browser("rust"));
+        assert!(channels.wanted().contains("rust"));
+        assert!(!channels.remove_browser("inconnu"));
+    }
+
+    #[test]
+    fn browser_channels_are_capped() {
+        let mut channels = Channels::default();
+        for i in 0..MAX_BROWSER_CHANNELS {
+            assert!(channels.add_browser(&format!("canal-{}", i)));
+        }
+        assert!(!channels.add_browser("un-de-trop"));
+        // Un canal déjà demandé accepte d'autres connexions, comme le canal par défaut
+        assert!(channels.add_browser("canal-0"));
+        assert!(channels.add_browser(DEFAULT_CHANNEL));
+
+        channels.remove_browser("canal-1");
+        channels.remove_browser(DEFAULT_CHANNEL);
+        assert!(channels.add_browser("un-de-trop"));
+        assert_eq!(channels.wanted().len(), MAX_BROWSER_CHANNELS + 1);
+    }
+}
@@ -0,0 +1,403 @@
+//! Ligne de commande et fichier de configuration
+//!
+//! Priorité des réglages : ligne de commande, puis variables d'environnement
+//! `ZETA2_*`, puis fichier TOML (`--config`, ou `zeta2.toml` s'il existe),
+//! puis valeurs par défaut. Tous les chemins et ports sont configurables pour
+//! pouvoir lancer plusieurs nœuds sur la même machine.
+
+use clap::{Args, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use zeta_proto::ws::LimitScope;
+
+/// Fichier de configuration chargé automatiquement s'il existe
+const DEFAULT_CONFIG_FILE: &str = "zeta2.toml";
+
+#[derive(Parser, Debug)]
+#[command(name = "zeta2", version, about = "Zeta2 - Réseau social décentralisé P2P")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub options: CliOptions,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Lance le nœud (commande par défaut)
+    Run,
+    /// Affiche le PeerId de l'identité locale (la génère si besoin)
+    Identity,
+    /// Affiche la configuration effective au format TOML
+    Config,
+}
+
+#[derive(Args, Debug)]
+pub struct CliOptions {
+    /// Fichier de configuration TOML
+    #[arg(short, long, global = true, env = "ZETA2_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Mode relay : écoute sur un port fixe et sert de relais aux autres nœuds
+    /// (`--relay=false` désactive celui du fichier de configuration)
+    #[arg(
+        long,
+        alias = "server",
+        global = true,
+        env = "ZETA2_RELAY",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new(),
+    )]
+    pub relay: Option<bool>,
+
+    /// Adresse multiaddr du relay auquel se connecter
+    #[arg(long, global = true, env = "ZETA2_RELAY_ADDR")]
+    pub relay_addr: Option<String>,
+
+    /// Nom affiché du nœud
+    #[arg(long, global = true, env = "ZETA2_NAME")]
+    pub name: Option<String>,
+
+    /// Fichier de la clé d'identité
+    #[arg(long, global = true, env = "ZETA2_IDENTITY_FILE")]
+    pub identity_file: Option<PathBuf>,
+
+    /// Fichier des bootstrap peers
+    #[arg(long, global = true, env = "ZETA2_BOOTSTRAP_FILE")]
+    pub bootstrap_file: Option<PathBuf>,
+
+    /// Port TCP P2P (défaut : 4001 en mode relay, aléatoire sinon)
+    #[arg(long, global = true, env = "ZETA2_P2P_PORT")]
+    pub p2p_port: Option<u16>,
+
+    /// Port de l'interface web et du WebSocket
+    #[arg(long, alias = "port", global = true, env = "ZETA2_WEB_PORT")]
+    pub web_port: Option<u16>,
+
+    /// Topic gossipsub
+    #[arg(long, global = true, env = "ZETA2_TOPIC")]
+    pub topic: Option<String>,
+
+    /// Dossier des fichiers statiques de l'interface web
+    #[arg(long, global = true, env = "ZETA2_STATIC_DIR")]
+    pub static_dir: Option<PathBuf>,
+
+    /// Journal de persistance des posts
+    #[arg(long, global = true, env = "ZETA2_POSTS_FILE")]
+    pub posts_file: Option<PathBuf>,
+
+    /// Nombre maximum de posts conservés
+    #[arg(long, global = true, env = "ZETA2_MAX_POSTS")]
+    pub max_posts: Option<usize>,
+
+    /// Ne pas persister les posts sur disque
+    #[arg(long, global = true, env = "ZETA2_IN_MEMORY")]
+    pub in_memory: bool,
+
+    /// Dossier des fichiers joints aux posts
+    #[arg(long, global = true, env = "ZETA2_BLOBS_DIR")]
+    pub blobs_dir: Option<PathBuf>,
+
+    /// Volume maximal des fichiers joints, en Mio
+    #[arg(long, global = true, env = "ZETA2_BLOB_QUOTA_MB")]
+    pub blob_quota_mb: Option<u64>,
+
+    /// URL WebSocket publique annoncée aux autres relais
+    #[arg(long, global = true, env = "ZETA2_PUBLIC_WS_URL")]
+    pub public_ws_url: Option<String>,
+
+    /// Canaux suivis en plus de `general`, séparés par des virgules
+    #[arg(long, global = true, env = "ZETA2_CHANNELS", value_delimiter = ',')]
+    pub channels: Option<Vec<String>>,
+
+    /// Publications par minute et par connexion WebSocket (0 : illimité)
+    #[arg(long, global = true, env = "ZETA2_RATE_LIMIT_CONNECTION")]
+    pub rate_limit_connection: Option<u32>,
+
+    /// Publications par minute et par adresse IP (0 : illimité)
+    #[arg(long, global = true, env = "ZETA2_RATE_LIMIT_IP")]
+    pub rate_limit_ip: Option<u32>,
+
+    /// Publications par minute et par identité (0 : illimité)
+    #[arg(long, global = true, env = "ZETA2_RATE_LIMIT_IDENTITY")]
+    pub rate_limit_identity: Option<u32>,
+
+    /// Messages gossip par minute et par identité signataire (0 : illimité)
+    #[arg(long, global = true, env = "ZETA2_RATE_LIMIT_PEER")]
+    pub rate_limit_peer: Option<u32>,
+
+    /// Proxys dont l'en-tête X-Forwarded-For est cru, séparés par des virgules
+    #[arg(long, global = true, env = "ZETA2_TRUSTED_PROXIES", value_delimiter = ',')]
+    pub trusted_proxies: Option<Vec<IpAddr>>,
+
+    /// Désactive le score des pairs gossipsub
+    #[arg(long, global = true, env = "ZETA2_NO_PEER_SCORING")]
+    pub no_peer_scoring: bool,
+}
+
+/// Configuration effective du nœud
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub relay: bool,
+    pub relay_addr: Option<String>,
+    pub name: Option<String>,
+    pub identity_file: PathBuf,
+    pub bootstrap_file: PathBuf,
+    pub p2p_port: Option<u16>,
+    pub web_port: u16,
+    pub topic: String,
+    pub static_dir: PathBuf,
+    pub posts_file: PathBuf,
+    pub max_posts: usize,
+    pub in_memory: bool,
+    pub blobs_dir: PathBuf,
+    /// Volume maximal des fichiers joints, en Mio
+    pub blob_quota_mb: u64,
+    pub public_ws_url: Option<String>,
+    /// Canaux suivis en plus de `general`
+    pub channels: Vec<String>,
+    /// Limites de publication par minute, 0 pour ne pas limiter
+    pub rate_limit_connection: u32,
+    pub rate_limit_ip: u32,
+    pub rate_limit_identity: u32,
+    pub rate_limit_peer: u32,
+    /// Proxys dont l'en-tête `X-Forwarded-For` est cru
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Score des pairs gossipsub (section `[peer_scoring]`)
+    pub peer_scoring: PeerScoring,
+}
+
+/// Réglages du score des pairs gossipsub
+///
+/// Le score d'un pair baisse surtout avec les messages rejetés à la
+/// validation (carré du nombre de rejets, qui décroît avec le temps) ; sous
+/// les seuils, le pair ne reçoit plus de gossip, puis plus de publications,
+/// puis ses messages sont ignorés. Les pairs les moins bien notés quittent le
+/// mesh au heartbeat suivant. Désactivé, les pairs connectés deviennent des
+/// pairs explicites gossipsub, qui échappent au score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerScoring {
+    pub enabled: bool,
+    /// Seuil sous lequel le pair ne reçoit plus de gossip (IHAVE/IWANT)
+    pub gossip_threshold: f64,
+    /// Seuil sous lequel on ne lui publie plus nos messages
+    pub publish_threshold: f64,
+    /// Seuil sous lequel ses messages sont ignorés
+    pub graylist_threshold: f64,
+    /// Score minimal pour accepter ses suggestions de pairs (PX)
+    pub accept_px_threshold: f64,
+    /// Score médian du mesh sous lequel on greffe de meilleurs pairs
+    pub opportunistic_graft_threshold: f64,
+    /// Poids de chaque topic suivi
+    pub topic_weight: f64,
+    /// Poids (négatif) des messages rejetés
+    pub invalid_message_weight: f64,
+    /// Part des rejets conservée chaque seconde
+    pub invalid_message_decay: f64,
+    /// Poids (positif) des messages reçus en premier d'un pair
+    pub first_delivery_weight: f64,
+    /// Plafond du compteur de premières livraisons
+    pub first_delivery_cap: f64,
+    /// Nombre de pairs tolérés derrière une même adresse IP
+    pub ip_colocation_threshold: f64,
+}
+
+impl Default for PeerScoring {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gossip_threshold: -10.0,
+            publish_threshold: -50.0,
+            graylist_threshold: -80.0,
+            accept_px_threshold: 10.0,
+            opportunistic_graft_threshold: 5.0,
+            topic_weight: 1.0,
+            invalid_message_weight: -10.0,
+            invalid_message_decay: 0.997,
+            first_delivery_weight: 1.0,
+            first_delivery_cap: 50.0,
+            ip_colocation_threshold: 10.0,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            relay: false,
+            relay_addr: None,
+            name: None,
+            identity_file: PathBuf::from("identity.key"),
+            bootstrap_file: PathBuf::from("bootstrap.txt"),
+            p2p_port: None,
+            web_port: 3030,
+            topic: "zeta2-social".to_string(),
+            static_dir: PathBuf::from("./static"),
+            posts_file: PathBuf::from("posts.jsonl"),
+            max_posts: 1000,
+            in_memory: false,
+            blobs_dir: PathBuf::from("blobs"),
+            blob_quota_mb: 512,
+            public_ws_url: None,
+            channels: Vec::new(),
+            rate_limit_connection: 10,
+            rate_limit_ip: 30,
+            rate_limit_identity: 10,
+            rate_limit_peer: 120,
+            trusted_proxies: Vec::new(),
+            peer_scoring: PeerScoring::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Charge le fichier de configuration puis applique la ligne de commande et l'environnement
+    pub fn load(options: CliOptions) -> Result<Self, Box<dyn Error>> {
+        let mut config = match &options.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => Self::default(),
+        };
+
+        if let Some(relay) = options.relay {
+            config.relay = relay;
+        }
+        config.in_memory |= options.in_memory;
+        if options.no_peer_scoring {
+            config.peer_scoring.enabled = false;
+        }
+        if options.relay_addr.is_some() {
+            config.relay_addr = options.relay_addr;
+        }
+        if options.name.is_some() {
+            config.name = options.name;
+        }
+        if let Some(path) = options.identity_file {
+            config.identity_file = path;
+        }
+        if let Some(path) = options.bootstrap_file {
+            config.bootstrap_file = path;
+        }
+        if options.p2p_port.is_some() {
+            config.p2p_port = options.p2p_port;
+        }
+        if let Some(port) = options.web_port {
+            config.web_port = port;
+        }
+        if let Some(topic) = options.topic {
+            config.topic = topic;
+        }
+        if let Some(dir) = options.static_dir {
+            config.static_dir = dir;
+        }
+        if let Some(path) = options.posts_file {
+            config.posts_file = path;
+        }
+        if let Some(max) = options.max_posts {
+            config.max_posts = max;
+        }
+        if let Some(dir) = options.blobs_dir {
+            config.blobs_dir = dir;
+        }
+        if let Some(quota) = options.blob_quota_mb {
+            config.blob_quota_mb = quota;
+        }
+        if options.public_ws_url.is_some() {
+            config.public_ws_url = options.public_ws_url;
+        }
+        if let Some(channels) = options.channels {
+            config.channels = channels;
+        }
+        if let Some(limit) = options.rate_limit_connection {
+            config.rate_limit_connection = limit;
+        }
+        if let Some(limit) = options.rate_limit_ip {
+            config.rate_limit_ip = limit;
+        }
+        if let Some(limit) = options.rate_limit_identity {
+            config.rate_limit_identity = limit;
+        }
+        if let Some(limit) = options.rate_limit_peer {
+            config.rate_limit_peer = limit;
+        }
+        if let Some(proxies) = options.trusted_proxies {
+            config.trusted_proxies = proxies;
+        }
+        config.channels = config
+            .channels
+            .iter()
+            .map(|name| zeta_proto::channel::normalize(name).ok_or_else(|| format!("nom de canal invalide: {}", name)))
+            .collect::<Result<_, _>>()?;
+        // Une rétention nulle écarterait chaque post dès son arrivée
+        if config.max_posts == 0 {
+            return Err("max_posts doit être supérieur à 0".into());
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("lecture de {}: {}", path.display(), e))?;
+        let config = toml::from_str(&content)
+            .map_err(|e| format!("configuration invalide {}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    /// Quota des fichiers joints en octets
+    pub fn blob_quota(&self) -> u64 {
+        self.blob_quota_mb.saturating_mul(1024 * 1024)
+    }
+
+    /// Limites de publication par portée
+    pub fn rate_limits(&self) -> [(LimitScope, u32); 4] {
+        [
+            (LimitScope::Connection, self.rate_limit_connection),
+            (LimitScope::Ip, self.rate_limit_ip),
+            (LimitScope::Identity, self.rate_limit_identity),
+            (LimitScope::Peer, self.rate_limit_peer),
+        ]
+    }
+
+    /// Port P2P effectif : fixe pour un relay, aléatoire pour un client
+    pub fn p2p_port(&self) -> u16 {
+        self.p2p_port.unwrap_or(if self.relay { 4001 } else { 0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(args: &[&str], file: &str) -> Result<Config, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("zeta2-test-{}.toml", uuid::Uuid::new_v4()));
+        fs::write(&path, file).unwrap();
+        let mut cli = Cli::try_parse_from([&["zeta2"], args].concat()).unwrap();
+        cli.options.config = Some(path.clone());
+        let config = Config::load(cli.options);
+        fs::remove_file(path).unwrap();
+        config
+    }
+
+    #[test]
+    fn cli_overrides_relay_from_file() {
+        assert!(load(&[], "relay = true").unwrap().relay);
+        assert!(load(&["--relay"], "").unwrap().relay);
+        assert!(!load(&["--relay=false"], "relay = true").unwrap().relay);
+        assert!(load(&["--relay=true"], "relay = false").unwrap().relay);
+    }
+
+    #[test]
+    fn zero_retention_is_rejected() {
+        assert!(load(&["--max-posts", "0"], "").is_err());
+        assert!(load(&[], "max_posts = 0").is_err());
+        assert_eq!(load(&["--max-posts", "5"], "max_posts = 0").unwrap().max_posts, 5);
+    }
+}
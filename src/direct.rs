@@ -0,0 +1,118 @@
+//! Boîtes de réception des messages directs
+//!
+//! Le relay garde les derniers messages chiffrés de chaque identité pour les
+//! remettre à sa prochaine connexion WebSocket. Il ne peut pas les lire :
+//! seuls l'expéditeur et le destinataire détiennent la clé. Le nombre de
+//! boîtes est borné : au-delà, celle qui a reçu son dernier message le plus
+//! tôt est oubliée.
+
+use std::collections::{HashMap, VecDeque};
+use zeta_proto::DirectMessage;
+
+/// Nombre de messages conservés par destinataire
+pub const MAX_DIRECT_PER_IDENTITY: usize = 100;
+
+/// Nombre de destinataires dont les messages sont conservés
+pub const MAX_DIRECT_RECIPIENTS: usize = 1024;
+
+#[derive(Default)]
+struct Inbox {
+    /// Messages du plus ancien au plus récent
+    messages: VecDeque<DirectMessage>,
+    /// Rang d'arrivée du dernier message, indépendant de sa date déclarée
+    last_received: u64,
+}
+
+#[derive(Default)]
+pub struct DirectInbox {
+    by_recipient: HashMap<String, Inbox>,
+    received: u64,
+}
+
+impl DirectInbox {
+    /// Range un message, retourne `false` s'il est déjà connu
+    pub fn add(&mut self, message: &DirectMessage) -> bool {
+        self.received += 1;
+        let inbox = self.by_recipient.entry(message.recipient.clone()).or_default();
+        if inbox.messages.iter().any(|known| known.id() == message.id()) {
+            return false;
+        }
+        inbox.messages.push_back(message.clone());
+        inbox.last_received = self.received;
+        if inbox.messages.len() > MAX_DIRECT_PER_IDENTITY {
+            inbox.messages.pop_front();
+        }
+        if self.by_recipient.len() > MAX_DIRECT_RECIPIENTS {
+            let oldest = self
+                .by_recipient
+                .iter()
+                .min_by_key(|(_, inbox)| inbox.last_received)
+                .map(|(recipient, _)| recipient.clone());
+            if let Some(recipient) = oldest {
+                self.by_recipient.remove(&recipient);
+            }
+        }
+        true
+    }
+
+    /// Messages envoyés ou reçus par une identité, du plus ancien au plus récent
+    pub fn conversation(&self, identity: &str) -> Vec<DirectMessage> {
+        let mut messages: Vec<DirectMessage> = self
+            .by_recipient
+            .iter()
+            .flat_map(|(recipient, inbox)| {
+                inbox.messages.iter().filter(move |m| recipient == identity || m.sender == identity)
+            })
+            .cloned()
+            .collect();
+        messages.sort_by_key(|m| m.timestamp);
+        messages
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_recipient.values().map(|inbox| inbox.messages.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(recipient: &str, nonce: &str) -> DirectMessage {
+        DirectMessage {
+            sender: "alice".to_string(),
+            recipient: recipient.to_string(),
+            timestamp: i64::MAX,
+            nonce: nonce.to_string(),
+            ciphertext: String::new(),
+            signature: String::new(),
+        }
+    }
+
+    #[test]
+    fn recipients_are_capped_by_arrival() {
+        let mut inbox = DirectInbox::default();
+        assert!(inbox.add(&message("bob", "n0")));
+        for i in 1..MAX_DIRECT_RECIPIENTS {
+            assert!(inbox.add(&message(&format!("r{}", i), &format!("n{}", i))));
+        }
+        // Bob reçoit un nouveau message : r1 devient la boîte la plus ancienne
+        assert!(inbox.add(&message("bob", "again")));
+        assert!(inbox.add(&message("newcomer", "last")));
+
+        assert_eq!(inbox.by_recipient.len(), MAX_DIRECT_RECIPIENTS);
+        assert_eq!(inbox.conversation("bob").len(), 2);
+        assert!(inbox.conversation("r1").is_empty());
+        assert_eq!(inbox.conversation("newcomer").len(), 1);
+    }
+
+    #[test]
+    fn messages_are_capped_per_recipient() {
+        let mut inbox = DirectInbox::default();
+        for i in 0..MAX_DIRECT_PER_IDENTITY + 5 {
+            inbox.add(&message("bob", &format!("n{}", i)));
+        }
+        assert!(!inbox.add(&message("bob", "n10")));
+        assert_eq!(inbox.len(), MAX_DIRECT_PER_IDENTITY);
+    }
+}
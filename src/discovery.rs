@@ -0,0 +1,60 @@
+//! Découverte de pairs et de relais via Kademlia
+//!
+//! Le DHT est amorcé avec les pairs de `bootstrap.txt`, puis entretenu par un
+//! bootstrap périodique et des marches aléatoires. Chaque relay s'annonce comme
+//! fournisseur de la clé `zeta2-relay`, ce qui permet aux nœuds de trouver des
+//! relais sans liste écrite à la main.
+//!
+//! Les relais annoncent aussi leur URL WebSocket publique sur gossipsub ; ces
+//! annonces alimentent l'annuaire `/api/relays` utilisé par les navigateurs.
+
+use libp2p::kad::{self, store::MemoryStore};
+use libp2p::{PeerId, StreamProtocol};
+use std::fs;
+use std::time::Duration;
+
+pub const KAD_PROTOCOL: StreamProtocol = StreamProtocol::new("/zeta2/kad/1.0.0");
+
+/// Clé DHT dont les relais sont fournisseurs
+pub const RELAY_PROVIDER_KEY: &str = "zeta2-relay";
+
+/// Intervalle entre deux bootstraps Kademlia (et ré-annonces de relay)
+pub const BOOTSTRAP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Intervalle entre deux marches aléatoires / recherches de relais
+pub const DISCOVERY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Intervalle entre deux annonces de l'URL WebSocket d'un relay
+pub const RELAY_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(120);
+
+/// Délai (secondes) après lequel un relay silencieux sort de l'annuaire
+pub const RELAY_EXPIRY_SECS: i64 = 900;
+
+/// Fichier écrit par `run_relay.sh` avec l'URL du tunnel WSS
+const WSS_URL_FILE: &str = "current_wss_url.txt";
+
+/// URL WebSocket publique du relay local : option explicite, sinon fichier du tunnel
+pub fn public_ws_url(configured: Option<&str>) -> Option<String> {
+    if let Some(url) = configured {
+        return Some(url.to_string());
+    }
+    let url = fs::read_to_string(WSS_URL_FILE).ok()?;
+    let url = url.trim();
+    (url.starts_with("ws://") || url.starts_with("wss://")).then(|| url.to_string())
+}
+
+pub fn relay_key() -> kad::RecordKey {
+    kad::RecordKey::new(&RELAY_PROVIDER_KEY)
+}
+
+pub fn new_behaviour(local_peer_id: PeerId, is_relay: bool) -> kad::Behaviour<MemoryStore> {
+    let mut config = kad::Config::new(KAD_PROTOCOL);
+    config.set_query_timeout(Duration::from_secs(30));
+
+    let mut kad = kad::Behaviour::with_config(local_peer_id, MemoryStore::new(local_peer_id), config);
+    // Les relais sont joignables publiquement : ils répondent aux requêtes DHT
+    if is_relay {
+        kad.set_mode(Some(kad::Mode::Server));
+    }
+    kad
+}
@@ -0,0 +1,374 @@
+//! Fil des posts indexé par identifiant, par empreinte de contenu et par
+//! conversation
+//!
+//! Toutes les sources (gossipsub, WebSocket, REST, synchronisation) passent
+//! par `NetworkState::ingest_post`, qui s'appuie sur cet index pour rejeter
+//! les doublons avant stockage et diffusion.
+//!
+//! Les posts supprimés laissent une pierre tombale (identifiant et auteur) :
+//! une synchronisation ultérieure ne peut pas les faire réapparaître. La
+//! première pierre tombale d'un identifiant l'emporte : la suppression d'un
+//! autre auteur ne peut ni retirer le post ni la remplacer.
+//!
+//! Les pierres tombales sont bornées : celles des posts connus suivent la
+//! rétention du fil, celles des posts encore inconnus sont limitées à
+//! `MAX_PENDING_TOMBSTONES`. Les plus anciennes sont oubliées en premier.
+
+use crate::Post;
+use std::collections::{HashMap, HashSet, VecDeque};
+use zeta_proto::{PostDelete, PostEdit, ThreadNode};
+
+/// Pierres tombales gardées pour des posts encore inconnus du relay
+pub const MAX_PENDING_TOMBSTONES: usize = 1024;
+
+#[derive(Default)]
+pub struct Feed {
+    /// Posts du plus récent au plus ancien
+    posts: Vec<Post>,
+    ids: HashSet<String>,
+    hashes: HashSet<String>,
+    /// Réponses directes de chaque post (par identifiant du parent)
+    replies: HashMap<String, Vec<String>>,
+    /// Posts supprimés : identifiant -> suppression signée
+    tombstones: HashMap<String, PostDelete>,
+    /// Pierres tombales de posts connus, de la plus ancienne à la plus récente
+    confirmed: VecDeque<String>,
+    /// Pierres tombales de posts encore inconnus, de la plus ancienne à la plus récente
+    pending: VecDeque<String>,
+}
+
+impl Feed {
+    /// Construit le fil à partir de posts déjà triés, en ignorant les doublons
+    /// et les posts supprimés
+    pub fn new(posts: Vec<Post>, tombstones: Vec<PostDelete>, max_posts: usize) -> Self {
+        let mut feed = Self::default();
+        // Les suppressions d'un autre auteur que celui du post sont ignorées
+        let authors: HashMap<&str, &str> = posts.iter().map(|p| (p.id.as_str(), p.author.as_str())).collect();
+        for delete in tombstones {
+            match authors.get(delete.post_id.as_str()) {
+                Some(author) if *author != delete.author => continue,
+                Some(_) => feed.remember(delete, true, max_posts),
+                None => feed.remember(delete, false, max_posts),
+            }
+        }
+        for post in posts {
+            feed.insert(post, max_posts);
+        }
+        feed
+    }
+
+    /// Le post (ou un post au contenu identique) est-il déjà connu ?
+    pub fn contains(&self, post: &Post) -> bool {
+        self.ids.contains(&post.id) || self.hashes.contains(&post.content_hash())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Post> {
+        if !self.ids.contains(id) {
+            return None;
+        }
+        self.posts.iter().find(|p| p.id == id)
+    }
+
+    /// Insère un post à sa place chronologique et applique la rétention.
+    /// Retourne `false` si le post est un doublon ou trop ancien pour être conservé.
+    pub fn insert(&mut self, post: Post, max_posts: usize) -> bool {
+        if self.contains(&post) {
+            return false;
+        }
+        if self.is_deleted(&post) {
+            // Le post supprimé est désormais connu : sa pierre tombale suit la rétention
+            if let Some(pos) = self.pending.iter().position(|id| id == &post.id) {
+                self.pending.remove(pos);
+                self.confirmed.push_back(post.id);
+                self.enforce_caps(max_posts);
+            }
+            return false;
+        }
+        // Une pierre tombale d'un autre auteur ne visait pas ce post
+        if self.tombstones.get(&post.id).is_some_and(|delete| delete.author != post.author) {
+            self.tombstones.remove(&post.id);
+            self.pending.retain(|id| id != &post.id);
+        }
+        let pos = self.posts.partition_point(|p| p.timestamp > post.timestamp);
+        if pos >= max_posts {
+            return false;
+        }
+
+        self.ids.insert(post.id.clone());
+        self.hashes.insert(post.content_hash());
+        if let Some(parent) = &post.reply_to {
+            self.replies.entry(parent.clone()).or_default().push(post.id.clone());
+        }
+        self.posts.insert(pos, post);
+
+        while self.posts.len() > max_posts {
+            if let Some(evicted) = self.posts.pop() {
+                self.unindex(&evicted);
+            }
+        }
+        true
+    }
+
+    /// Le post a-t-il été supprimé par son auteur ?
+    pub fn is_deleted(&self, post: &Post) -> bool {
+        self.tombstones.get(&post.id).is_some_and(|delete| delete.author == post.author)
+    }
+
+    /// Auteur de la suppression mémorisée pour un identifiant
+    pub fn deleted_by(&self, id: &str) -> Option<&str> {
+        self.tombstones.get(id).map(|delete| delete.author.as_str())
+    }
+
+    /// Pierres tombales conservées, des posts connus puis des posts inconnus
+    pub fn tombstones(&self) -> Vec<PostDelete> {
+        self.confirmed
+            .iter()
+            .chain(self.pending.iter())
+            .filter_map(|id| self.tombstones.get(id))
+            .cloned()
+            .collect()
+    }
+
+    pub fn tombstone_count(&self) -> usize {
+        self.tombstones.len()
+    }
+
+    /// Applique une modification qui remplace la précédente, retourne le post modifié
+    pub fn apply_edit(&mut self, edit: PostEdit) -> Option<&Post> {
+        let post = self.posts.iter_mut().find(|p| p.id == edit.post_id)?;
+        let newer = post.edit.as_ref().is_none_or(|current| edit.supersedes(current));
+        if !edit.applies_to(post) || !newer {
+            return None;
+        }
+        post.edit = Some(Box::new(edit));
+        Some(post)
+    }
+
+    /// Retire un post et mémorise la suppression, même si le post est inconnu
+    /// (il pourrait arriver plus tard par synchronisation). Retourne `false`
+    /// si l'identifiant est déjà supprimé ou si le post connu est d'un autre
+    /// auteur.
+    pub fn delete(&mut self, delete: &PostDelete, max_posts: usize) -> bool {
+        if self.tombstones.contains_key(&delete.post_id) {
+            return false;
+        }
+        let known = match self.posts.iter().position(|p| p.id == delete.post_id) {
+            Some(pos) if !delete.applies_to(&self.posts[pos]) => return false,
+            Some(pos) => {
+                let removed = self.posts.remove(pos);
+                self.unindex(&removed);
+                true
+            }
+            None => false,
+        };
+        self.remember(delete.clone(), known, max_posts);
+        true
+    }
+
+    fn remember(&mut self, delete: PostDelete, known: bool, max_posts: usize) {
+        if self.tombstones.contains_key(&delete.post_id) {
+            return;
+        }
+        let queue = if known { &mut self.confirmed } else { &mut self.pending };
+        queue.push_back(delete.post_id.clone());
+        self.tombstones.insert(delete.post_id.clone(), delete);
+        self.enforce_caps(max_posts);
+    }
+
+    /// Oublie les pierres tombales les plus anciennes au-delà des limites
+    fn enforce_caps(&mut self, max_posts: usize) {
+        while self.confirmed.len() > max_posts {
+            if let Some(id) = self.confirmed.pop_front() {
+                self.tombstones.remove(&id);
+            }
+        }
+        while self.pending.len() > MAX_PENDING_TOMBSTONES {
+            if let Some(id) = self.pending.pop_front() {
+                self.tombstones.remove(&id);
+            }
+        }
+    }
+
+    fn unindex(&mut self, post: &Post) {
+        self.ids.remove(&post.id);
+        self.hashes.remove(&post.content_hash());
+        if let Some(parent) = &post.reply_to {
+            if let Some(children) = self.replies.get_mut(parent) {
+                children.retain(|id| id != &post.id);
+                if children.is_empty() {
+                    self.replies.remove(parent);
+                }
+            }
+        }
+    }
+
+    /// Un post et l'arbre de ses réponses connues
+    pub fn thread(&self, id: &str) -> Option<ThreadNode> {
+        let post = self.get(id)?;
+        let replies = |parent: &str| -> Vec<&Post> {
+            self.replies
+                .get(parent)
+                .map(|children| children.iter().filter_map(|id| self.get(id)).collect())
+                .unwrap_or_default()
+        };
+        Some(ThreadNode::build(post, &replies))
+    }
+
+    pub fn all(&self) -> &[Post] {
+        &self.posts
+    }
+
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.posts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(id: &str, author: &str, timestamp: i64) -> Post {
+        Post {
+            id: id.to_string(),
+            author: author.to_string(),
+            content: format!("{} de {}", id, author),
+            timestamp,
+            ..Default::default()
+        }
+    }
+
+    fn delete(post_id: &str, author: &str) -> PostDelete {
+        PostDelete {
+            post_id: post_id.to_string(),
+            deleted_at: 0,
+            author: author.to_string(),
+            signature: String::new(),
+        }
+    }
+
+    fn edit(content: &str, edited_at: i64, signature: &str) -> PostEdit {
+        PostEdit {
+            post_id: "p1".to_string(),
+            content: content.to_string(),
+            edited_at,
+            author: "alice".to_string(),
+            signature: signature.to_string(),
+        }
+    }
+
+    fn current_content(feed: &Feed) -> &str {
+        feed.get("p1").unwrap().current_content()
+    }
+
+    #[test]
+    fn later_edit_wins_within_the_same_second() {
+        let mut feed = Feed::new(vec![post("p1", "alice", 1)], Vec::new(), 10);
+        assert!(feed.apply_edit(edit("premier", 1_000, "aa")).is_some());
+        assert!(feed.apply_edit(edit("second", 1_400, "00")).is_some());
+        assert!(feed.apply_edit(edit("ancien", 1_200, "ff")).is_none());
+        assert_eq!(current_content(&feed), "second");
+    }
+
+    #[test]
+    fn concurrent_edits_converge_whatever_the_order() {
+        let (a, b) = (edit("a", 1_000, "0a"), edit("b", 1_000, "0b"));
+        for order in [[a.clone(), b.clone()], [b, a]] {
+            let mut feed = Feed::new(vec![post("p1", "alice", 1)], Vec::new(), 10);
+            for edit in order {
+                feed.apply_edit(edit);
+            }
+            assert_eq!(current_content(&feed), "b");
+        }
+        // La même modification reçue deux fois n'est appliquée qu'une fois
+        let mut feed = Feed::new(vec![post("p1", "alice", 1)], Vec::new(), 10);
+        assert!(feed.apply_edit(edit("a", 1_000, "0a")).is_some());
+        assert!(feed.apply_edit(edit("a", 1_000, "0a")).is_none());
+    }
+
+    #[test]
+    fn delete_from_another_author_keeps_the_post() {
+        let mut feed = Feed::new(vec![post("p1", "alice", 1)], Vec::new(), 10);
+
+        assert!(!feed.delete(&delete("p1", "mallory"), 10));
+        assert!(feed.get("p1").is_some());
+        assert_eq!(feed.deleted_by("p1"), None);
+
+        assert!(feed.delete(&delete("p1", "alice"), 10));
+        assert!(feed.get("p1").is_none());
+    }
+
+    #[test]
+    fn delete_from_another_author_keeps_the_tombstone() {
+        let mut feed = Feed::new(vec![post("p1", "alice", 1)], Vec::new(), 10);
+        assert!(feed.delete(&delete("p1", "alice"), 10));
+
+        assert!(!feed.delete(&delete("p1", "mallory"), 10));
+        assert_eq!(feed.deleted_by("p1"), Some("alice"));
+        assert!(!feed.insert(post("p1", "alice", 1), 10));
+    }
+
+    #[test]
+    fn replayed_tombstones_keep_the_first_author() {
+        let tombstones = vec![delete("p1", "alice"), delete("p1", "mallory")];
+        let feed = Feed::new(vec![post("p1", "alice", 1)], tombstones, 10);
+
+        assert_eq!(feed.deleted_by("p1"), Some("alice"));
+        assert!(feed.get("p1").is_none());
+    }
+
+    #[test]
+    fn replayed_tombstones_skip_another_author() {
+        let tombstones = vec![delete("p1", "mallory"), delete("p1", "alice")];
+        let feed = Feed::new(vec![post("p1", "alice", 1)], tombstones, 10);
+
+        assert_eq!(feed.deleted_by("p1"), Some("alice"));
+        assert!(feed.get("p1").is_none());
+    }
+
+    #[test]
+    fn tombstone_from_another_author_does_not_block_the_post() {
+        let mut feed = Feed::default();
+        assert!(feed.delete(&delete("p1", "mallory"), 10));
+
+        assert!(feed.insert(post("p1", "alice", 1), 10));
+        assert_eq!(feed.deleted_by("p1"), None);
+        assert!(feed.delete(&delete("p1", "alice"), 10));
+        assert!(feed.get("p1").is_none());
+    }
+
+    #[test]
+    fn tombstones_are_capped() {
+        let mut feed = Feed::new(vec![post("p1", "alice", 1), post("p2", "alice", 2)], Vec::new(), 2);
+        assert!(feed.delete(&delete("p1", "alice"), 2));
+        assert!(feed.delete(&delete("p2", "alice"), 2));
+        assert!(feed.insert(post("p3", "alice", 3), 2));
+        assert!(feed.delete(&delete("p3", "alice"), 2));
+        assert_eq!(feed.deleted_by("p1"), None);
+        assert_eq!(feed.deleted_by("p3"), Some("alice"));
+
+        for i in 0..MAX_PENDING_TOMBSTONES + 10 {
+            assert!(feed.delete(&delete(&format!("unknown-{}", i), "mallory"), 2));
+        }
+        assert_eq!(feed.tombstone_count(), 2 + MAX_PENDING_TOMBSTONES);
+        assert_eq!(feed.deleted_by("unknown-0"), None);
+        // Les suppressions de posts inconnus n'évincent pas celles des posts connus
+        assert_eq!(feed.deleted_by("p2"), Some("alice"));
+    }
+
+    #[test]
+    fn pending_tombstone_is_confirmed_when_the_post_arrives() {
+        let mut feed = Feed::default();
+        assert!(feed.delete(&delete("p1", "alice"), 10));
+        assert!(!feed.insert(post("p1", "alice", 1), 10));
+
+        for i in 0..MAX_PENDING_TOMBSTONES {
+            feed.delete(&delete(&format!("unknown-{}", i), "mallory"), 10);
+        }
+        assert_eq!(feed.deleted_by("p1"), Some("alice"));
+        assert_eq!(feed.tombstones().first().map(|d| d.post_id.as_str()), Some("p1"));
+    }
+}
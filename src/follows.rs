@@ -0,0 +1,69 @@
+//! Abonnements entre identités
+//!
+//! Chaque relay garde, pour chaque identité, l'ensemble des auteurs qu'elle
+//! suit. Abonnements et désabonnements sont signés et datés : pour un couple
+//! abonné/auteur, seul le message le plus récent compte, quel que soit
+//! l'ordre d'arrivée par gossipsub ou synchronisation.
+
+use std::collections::HashMap;
+use zeta_proto::{Follow, NetworkMessage};
+
+#[derive(Default)]
+pub struct FollowGraph {
+    /// abonné -> auteur -> (abonné ?, dernier message signé)
+    by_follower: HashMap<String, HashMap<String, (bool, Follow)>>,
+}
+
+impl FollowGraph {
+    /// Applique un abonnement ou un désabonnement, retourne `false` s'il
+    /// n'est pas plus récent que le dernier connu pour ce couple
+    pub fn apply(&mut self, follow: &Follow, following: bool) -> bool {
+        let entries = self.by_follower.entry(follow.follower.clone()).or_default();
+        if entries.get(&follow.followee).is_some_and(|(_, known)| known.timestamp >= follow.timestamp) {
+            return false;
+        }
+        entries.insert(follow.followee.clone(), (following, follow.clone()));
+        true
+    }
+
+    /// Auteurs suivis par une identité, triés
+    pub fn following(&self, follower: &str) -> Vec<String> {
+        let mut list: Vec<String> = self
+            .by_follower
+            .get(follower)
+            .map(|entries| entries.iter().filter(|(_, (following, _))| *following).map(|(id, _)| id.clone()).collect())
+            .unwrap_or_default();
+        list.sort();
+        list
+    }
+
+    pub fn is_following(&self, follower: &str, followee: &str) -> bool {
+        self.by_follower
+            .get(follower)
+            .and_then(|entries| entries.get(followee))
+            .is_some_and(|(following, _)| *following)
+    }
+
+    /// Derniers messages connus, désabonnements compris, pour réécrire le journal
+    pub fn messages(&self) -> Vec<NetworkMessage> {
+        self.by_follower
+            .values()
+            .flat_map(|entries| entries.values())
+            .map(|(following, follow)| follow_message(follow.clone(), *following))
+            .collect()
+    }
+
+    /// Nombre de couples abonné/auteur connus
+    pub fn len(&self) -> usize {
+        self.by_follower.values().map(HashMap::len).sum()
+    }
+}
+
+/// Message gossip correspondant à un abonnement ou un désabonnement
+pub fn follow_message(follow: Follow, following: bool) -> NetworkMessage {
+    if following {
+        NetworkMessage::Follow(follow)
+    } else {
+        NetworkMessage::Unfollow(follow)
+    }
+}
@@ -1,50 +1,60 @@
 use libp2p::{
+    dcutr,
     futures::StreamExt,
     gossipsub::{self, IdentTopic, MessageAuthenticity},
     identify,
     identity::Keypair,
     kad::{self, store::MemoryStore},
-    mdns, noise, ping,
-    swarm::{NetworkBehaviour, SwarmEvent},
+    mdns,
+    multiaddr::Protocol,
+    noise, ping, relay, request_response,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour, SwarmEvent},
     tcp, yamux, Multiaddr, PeerId, SwarmBuilder,
 };
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use clap::Parser;
+use std::collections::{BTreeSet, HashMap};
 use std::error::Error;
 use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{broadcast, mpsc, RwLock};
-use tracing::{error, info, warn};
+use tokio::sync::{broadcast, mpsc, Notify, RwLock};
+use tracing::{debug, error, info, warn};
 use std::io::{BufRead, BufReader};
 
+mod blobs;
+mod blocks;
+mod channels;
+mod config;
+mod direct;
+mod discovery;
+mod feed;
+mod follows;
+mod profiles;
+mod ratelimit;
+mod reactions;
+mod scoring;
+mod signing;
+mod storage;
+mod sync;
 mod web_server;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Post {
-    pub id: String,
-    pub author: String,
-    pub author_name: String,
-    pub content: String,
-    pub timestamp: i64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum NetworkMessage {
-    Post(Post),
-    Heartbeat,
-    PeerJoined { peer_id: String, name: String },
-    PeerLeft { peer_id: String },
-}
+use blobs::BlobStore;
+use channels::Channels;
+use direct::DirectInbox;
+use feed::Feed;
+use follows::FollowGraph;
+use profiles::ProfileCache;
+use ratelimit::RateLimiter;
+use reactions::Reactions;
+use storage::{JsonlPostStore, MemoryPostStore, PostStore};
+use zeta_proto::envelope::{self, Decoded};
+use zeta_proto::ws::{LimitScope, ServerFrame};
+use zeta_proto::blob::{self, BlockRequest, BlockResponse};
+use zeta_proto::{channel, validation, SyncRequest, SyncResponse};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PeerInfo {
-    pub peer_id: String,
-    pub address: String,
-    pub name: Option<String>,
-    pub is_browser: bool,
-}
+pub use zeta_proto::{Attachment, DirectMessage, Follow, NetworkMessage, PeerInfo, Post, PostDelete, PostEdit, Profile, Reaction, RelayInfo};
 
 #[derive(NetworkBehaviour)]
 struct ZetaBehaviour {
@@ -53,66 +63,735 @@ struct ZetaBehaviour {
     ping: ping::Behaviour,
     identify: identify::Behaviour,
     kad: kad::Behaviour<MemoryStore>,
+    sync: sync::Behaviour,
+    blocks: blocks::Behaviour,
+    // Serveur circuit relay v2 (uniquement en mode --relay)
+    relay_server: Toggle<relay::Behaviour>,
+    // Client circuit relay v2 + hole punching
+    relay_client: relay::client::Behaviour,
+    dcutr: dcutr::Behaviour,
+}
+
+/// Résultat de l'ingestion d'un post
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ingest {
+    Added,
+    Duplicate,
+    Invalid,
+}
+
+/// Compteurs des messages gossip non interprétés
+#[derive(Debug, Default)]
+pub struct MessageStats {
+    /// Messages illisibles (enveloppe ou contenu invalide)
+    pub malformed: AtomicU64,
+    /// Messages lisibles mais refusés (longueur, date, signature), non relayés
+    pub rejected: AtomicU64,
+    /// Types ou versions inconnus, relayés sans traitement
+    pub unknown: AtomicU64,
+}
+
+impl MessageStats {
+    pub fn snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "malformed": self.malformed.load(Ordering::Relaxed),
+            "rejected": self.rejected.load(Ordering::Relaxed),
+            "unknown": self.unknown.load(Ordering::Relaxed),
+        })
+    }
 }
 
 #[derive(Clone)]
 pub struct NetworkState {
     pub peers: Arc<RwLock<HashMap<String, PeerInfo>>>,
-    pub posts: Arc<RwLock<Vec<Post>>>,
+    pub posts: Arc<RwLock<Feed>>,
+    /// Réactions agrégées par post
+    pub reactions: Arc<RwLock<Reactions>>,
+    /// Auteurs suivis par chaque identité
+    pub follows: Arc<RwLock<FollowGraph>>,
+    /// Messages directs chiffrés en attente de remise
+    pub directs: Arc<RwLock<DirectInbox>>,
+    /// Dernier profil signé de chaque identité
+    pub profiles: Arc<RwLock<ProfileCache>>,
+    /// Canaux suivis par le nœud et ses navigateurs
+    pub channels: Arc<RwLock<Channels>>,
+    /// Réveille la boucle du swarm quand les canaux suivis changent
+    pub channels_changed: Arc<Notify>,
     pub local_peer_id: PeerId,
     pub local_name: String,
+    pub local_key: Keypair,
+    /// Clé privée ed25519 utilisée pour signer les posts locaux
+    local_secret: [u8; 32],
     // Channel pour diffuser aux clients WebSocket
-    pub ws_broadcast: broadcast::Sender<String>,
+    pub ws_broadcast: broadcast::Sender<ServerFrame>,
+    /// Annuaire des autres relais (URL WebSocket)
+    pub relays: Arc<RwLock<HashMap<String, RelayInfo>>>,
+    /// Stockage persistant des posts
+    pub store: Arc<dyn PostStore>,
+    /// Fichiers joints aux posts
+    pub blobs: Arc<BlobStore>,
+    /// Fichiers à demander aux pairs
+    pub wanted_blobs: Arc<RwLock<BTreeSet<String>>>,
+    /// Réveille la boucle du swarm quand des fichiers manquent
+    pub blobs_wanted: Arc<Notify>,
+    /// Nombre maximum de posts conservés
+    pub max_posts: usize,
+    /// Messages gossip illisibles ou inconnus
+    pub message_stats: Arc<MessageStats>,
+    /// Limites de débit des publications
+    pub limits: Arc<RateLimiter>,
+    /// Dernier score gossipsub de chaque pair, relevé périodiquement
+    pub peer_scores: Arc<RwLock<HashMap<String, f64>>>,
 }
 
 impl NetworkState {
-    fn new(local_peer_id: PeerId, local_name: String) -> Self {
+    fn new(
+        local_key: Keypair,
+        local_name: String,
+        store: Arc<dyn PostStore>,
+        blobs: Arc<BlobStore>,
+        limits: Arc<RateLimiter>,
+        max_posts: usize,
+        channels: Vec<String>,
+    ) -> Self {
         let (ws_broadcast, _) = broadcast::channel(100);
+
+        // Recharger les suppressions puis les posts sauvegardés
+        let tombstones = store.load_tombstones().unwrap_or_else(|e| {
+            error!("❌ Impossible de recharger les suppressions: {}", e);
+            Vec::new()
+        });
+        let posts = match store.load() {
+            Ok(posts) => Feed::new(posts, tombstones, max_posts),
+            Err(e) => {
+                error!("❌ Impossible de recharger les posts: {}", e);
+                Feed::default()
+            }
+        };
+        if !posts.is_empty() {
+            info!("💾 {} post(s) rechargé(s) depuis le stockage", posts.len());
+        }
+
+        // Recharger les réactions aux posts conservés
+        let mut reactions = Reactions::default();
+        match store.load_reactions() {
+            Ok(saved) => {
+                for reaction in saved.iter().filter(|r| r.verify() && posts.get(&r.post_id).is_some()) {
+                    reactions.add(reaction);
+                }
+            }
+            Err(e) => error!("❌ Impossible de recharger les réactions: {}", e),
+        }
+
+        // Rejouer le journal des abonnements
+        let mut follows = FollowGraph::default();
+        match store.load_follows() {
+            Ok(messages) => {
+                for msg in messages {
+                    let (follow, following) = match msg {
+                        NetworkMessage::Follow(follow) => (follow, true),
+                        NetworkMessage::Unfollow(follow) => (follow, false),
+                        _ => continue,
+                    };
+                    if follow.verify(following) {
+                        follows.apply(&follow, following);
+                    }
+                }
+            }
+            Err(e) => error!("❌ Impossible de recharger les abonnements: {}", e),
+        }
+
+        let mut profiles = ProfileCache::default();
+        match store.load_profiles() {
+            Ok(saved) => {
+                for profile in saved.iter().filter(|p| p.verify()) {
+                    profiles.apply(profile);
+                }
+            }
+            Err(e) => error!("❌ Impossible de recharger les profils: {}", e),
+        }
+
         Self {
             peers: Arc::new(RwLock::new(HashMap::new())),
-            posts: Arc::new(RwLock::new(Vec::new())),
-            local_peer_id,
+            posts: Arc::new(RwLock::new(posts)),
+            reactions: Arc::new(RwLock::new(reactions)),
+            follows: Arc::new(RwLock::new(follows)),
+            directs: Arc::new(RwLock::new(DirectInbox::default())),
+            profiles: Arc::new(RwLock::new(profiles)),
+            channels: Arc::new(RwLock::new(Channels::new(channels))),
+            channels_changed: Arc::new(Notify::new()),
+            local_peer_id: PeerId::from(local_key.public()),
             local_name,
+            local_secret: signing::secret_key(&local_key).expect("identité ed25519 vérifiée au chargement"),
+            local_key,
             ws_broadcast,
+            relays: Arc::new(RwLock::new(HashMap::new())),
+            store,
+            blobs,
+            wanted_blobs: Arc::new(RwLock::new(BTreeSet::new())),
+            blobs_wanted: Arc::new(Notify::new()),
+            max_posts,
+            message_stats: Arc::new(MessageStats::default()),
+            limits,
+            peer_scores: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
-    pub async fn add_peer(&self, peer_info: PeerInfo) {
+    /// Crée un post signé avec l'identité du nœud local, éventuellement en
+    /// réponse à un autre ; `channel` doit être un nom de canal normalisé
+    pub async fn new_local_post(
+        &self,
+        author_name: String,
+        content: String,
+        reply_to: Option<String>,
+        channel: Option<String>,
+        attachments: Vec<Attachment>,
+    ) -> Post {
+        // La conversation (et par défaut le canal) est celle du parent s'il est connu
+        let (thread_root, parent_channel) = match &reply_to {
+            Some(parent) => match self.posts.read().await.get(parent) {
+                Some(p) => (Some(p.thread_id().to_string()), p.channel.clone()),
+                None => (Some(parent.clone()), None),
+            },
+            None => (None, None),
+        };
+        // Le canal par défaut reste implicite, comme pour les posts antérieurs
+        let channel = channel.or(parent_channel).filter(|c| c != channel::DEFAULT_CHANNEL);
+        let mut post = Post {
+            id: uuid::Uuid::new_v4().to_string(),
+            author: self.local_peer_id.to_string(),
+            author_name,
+            content,
+            timestamp: chrono::Utc::now().timestamp(),
+            reply_to,
+            thread_root,
+            channel,
+            attachments,
+            ..Default::default()
+        };
+        post.sign(&self.local_secret);
+        post
+    }
+
+    pub async fn add_peer(&self, mut peer_info: PeerInfo) {
         let peer_id = peer_info.peer_id.clone();
+        // Le nom d'un profil signé l'emporte sur le nom par défaut
+        if let Some(name) = self.profiles.read().await.display_name(&peer_id) {
+            peer_info.name = Some(name);
+        }
         self.peers.write().await.insert(peer_id.clone(), peer_info);
         // Notifier les clients WebSocket
-        let msg = serde_json::json!({
-            "type": "peer_joined",
-            "peer_id": peer_id
-        });
-        let _ = self.ws_broadcast.send(msg.to_string());
+        let _ = self.ws_broadcast.send(ServerFrame::PeerJoined { peer_id });
     }
 
     pub async fn remove_peer(&self, peer_id: &str) {
         self.peers.write().await.remove(peer_id);
-        let msg = serde_json::json!({
-            "type": "peer_left", 
-            "peer_id": peer_id
-        });
-        let _ = self.ws_broadcast.send(msg.to_string());
+        let frame = ServerFrame::PeerLeft { peer_id: peer_id.to_string() };
+        let _ = self.ws_broadcast.send(frame);
     }
 
-    pub async fn add_post(&self, post: Post) {
-        let mut posts = self.posts.write().await;
-        posts.insert(0, post.clone());
-        if posts.len() > 1000 {
-            posts.truncate(1000);
+    /// Point d'entrée unique des posts, quelle que soit leur source :
+    /// vérification, déduplication, stockage puis diffusion WebSocket.
+    pub async fn ingest_post(&self, post: Post) -> Ingest {
+        let result = self.store_post(&post).await;
+        if result == Ingest::Added {
+            // Notifier les clients WebSocket
+            let _ = self.ws_broadcast.send(ServerFrame::NewPost { post });
         }
-        // Notifier les clients WebSocket
-        let msg = serde_json::json!({
-            "type": "new_post",
-            "post": post
-        });
-        let _ = self.ws_broadcast.send(msg.to_string());
+        result
     }
 
-    pub async fn broadcast_to_ws(&self, message: &str) {
-        let _ = self.ws_broadcast.send(message.to_string());
+    /// Vérifie, déduplique et stocke un post sans le diffuser aux navigateurs
+    async fn store_post(&self, post: &Post) -> Ingest {
+        if !post.verify() {
+            return Ingest::Invalid;
+        }
+
+        let mut feed = self.posts.write().await;
+        if !feed.insert(post.clone(), self.max_posts) {
+            drop(feed);
+            // Un post déjà connu peut apporter une modification plus récente (synchronisation)
+            if let Some(edit) = &post.edit {
+                self.ingest_edit((**edit).clone()).await;
+            }
+            return Ingest::Duplicate;
+        }
+
+        if let Err(e) = self.store.append(post) {
+            error!("❌ Erreur sauvegarde du post {}: {}", post.id, e);
+        }
+        if self.store.should_compact(self.max_posts) {
+            if let Err(e) = self.store.compact(feed.all()) {
+                error!("❌ Erreur compaction du stockage: {}", e);
+            }
+        }
+        drop(feed);
+
+        // Récupérer auprès des pairs les fichiers joints qui nous manquent
+        for attachment in &post.attachments {
+            if !self.blobs.contains(&attachment.cid) {
+                self.want_blob(&attachment.cid).await;
+            }
+        }
+        Ingest::Added
+    }
+
+    /// Le fichier est joint à un post connu
+    pub async fn is_attached(&self, cid: &str) -> bool {
+        self.posts.read().await.all().iter().any(|post| post.attachments.iter().any(|a| a.cid == cid))
+    }
+
+    /// Demande un fichier manquant aux pairs connectés
+    pub async fn want_blob(&self, cid: &str) {
+        if self.wanted_blobs.write().await.insert(cid.to_string()) {
+            self.blobs_wanted.notify_one();
+        }
+    }
+
+    /// Fichiers joints d'un post local, décrits d'après le stockage ; `None`
+    /// si l'un d'eux n'a pas été envoyé au relay ou s'ils sont trop nombreux
+    pub fn local_attachments(&self, requested: Vec<Attachment>) -> Option<Vec<Attachment>> {
+        if requested.len() > blob::MAX_ATTACHMENTS {
+            return None;
+        }
+        requested
+            .into_iter()
+            .map(|a| self.blobs.attachment(&a.cid, a.name).filter(Attachment::is_valid))
+            .collect()
+    }
+
+    /// Répond à une demande de bloc d'un pair
+    pub fn answer_block(&self, request: BlockRequest) -> BlockResponse {
+        let data = self.blobs.get(&request.cid).map(|(bytes, _)| hex::encode(bytes));
+        BlockResponse { cid: request.cid, data }
+    }
+
+    /// Range un bloc reçu s'il correspond au CID demandé
+    pub async fn store_block(&self, response: BlockResponse) -> Result<Option<Attachment>, blobs::BlobError> {
+        let Some(bytes) = response.data.and_then(|data| hex::decode(data).ok()) else {
+            return Ok(None);
+        };
+        if !self.wanted_blobs.read().await.contains(&response.cid) || blob::cid(&bytes) != response.cid {
+            return Ok(None);
+        }
+        let attachment = self.blobs.put(&bytes, None)?;
+        self.wanted_blobs.write().await.remove(&response.cid);
+        Ok(Some(attachment))
+    }
+
+    /// Abandonne un fichier qu'aucun pair n'a fourni ; il sera redemandé au
+    /// prochain post ou à la prochaine requête HTTP qui le référence
+    pub async fn forget_blob(&self, cid: &str) {
+        self.wanted_blobs.write().await.remove(cid);
+    }
+
+    /// Crée une réaction signée avec l'identité du nœud local
+    pub fn new_local_reaction(&self, post_id: String, emoji: String) -> Reaction {
+        Reaction::new(post_id, emoji, &self.local_secret)
+    }
+
+    /// Point d'entrée unique des réactions : vérification, déduplication par
+    /// auteur, stockage puis diffusion des nouveaux compteurs
+    pub async fn ingest_reaction(&self, reaction: &Reaction) -> Ingest {
+        let ingest = self.store_reaction(reaction).await;
+        if ingest == Ingest::Added {
+            let counts = self.reactions.read().await.counts(&reaction.post_id);
+            let frame = ServerFrame::Reactions { post_id: reaction.post_id.clone(), counts };
+            let _ = self.ws_broadcast.send(frame);
+        }
+        ingest
+    }
+
+    /// Vérifie, compte et stocke une réaction sans la diffuser aux clients WebSocket
+    async fn store_reaction(&self, reaction: &Reaction) -> Ingest {
+        if !reaction.verify() {
+            return Ingest::Invalid;
+        }
+        // Seules les réactions aux posts conservés sont comptées
+        let feed = self.posts.read().await;
+        if feed.get(&reaction.post_id).is_none() {
+            return Ingest::Duplicate;
+        }
+
+        let mut reactions = self.reactions.write().await;
+        if !reactions.add(reaction) {
+            return Ingest::Duplicate;
+        }
+        if reactions.len() > self.max_posts {
+            reactions.retain_posts(|post_id| feed.get(post_id).is_some());
+        }
+        if let Err(e) = self.store.append_reaction(reaction) {
+            error!("❌ Erreur sauvegarde de la réaction au post {}: {}", reaction.post_id, e);
+        }
+        if let Err(e) = self.store.compact_reactions(&reactions.all()) {
+            error!("❌ Erreur compaction des réactions: {}", e);
+        }
+        Ingest::Added
+    }
+
+    /// Modification signée d'un post du nœud local
+    pub fn new_local_edit(&self, post_id: String, content: String) -> PostEdit {
+        PostEdit::new(post_id, content, chrono::Utc::now().timestamp_millis(), &self.local_secret)
+    }
+
+    /// Suppression signée d'un post du nœud local
+    pub fn new_local_delete(&self, post_id: String) -> PostDelete {
+        PostDelete::new(post_id, chrono::Utc::now().timestamp(), &self.local_secret)
+    }
+
+    /// Applique la modification d'un post par son auteur, puis la stocke et la diffuse.
+    /// `Duplicate` couvre les posts inconnus et les modifications déjà appliquées.
+    pub async fn ingest_edit(&self, edit: PostEdit) -> Ingest {
+        if !edit.verify() {
+            return Ingest::Invalid;
+        }
+
+        let mut feed = self.posts.write().await;
+        let Some(post) = feed.apply_edit(edit).cloned() else {
+            return Ingest::Duplicate;
+        };
+        if let Err(e) = self.store.append(&post) {
+            error!("❌ Erreur sauvegarde de la modification de {}: {}", post.id, e);
+        }
+        if self.store.should_compact(self.max_posts) {
+            if let Err(e) = self.store.compact(feed.all()) {
+                error!("❌ Erreur compaction du stockage: {}", e);
+            }
+        }
+        drop(feed);
+
+        let _ = self.ws_broadcast.send(ServerFrame::PostUpdated { post });
+        Ingest::Added
+    }
+
+    /// Supprime un post à la demande de son auteur et garde une pierre tombale
+    pub async fn ingest_delete(&self, delete: PostDelete) -> Ingest {
+        if !delete.verify() {
+            return Ingest::Invalid;
+        }
+
+        let mut feed = self.posts.write().await;
+        // Un post connu ne peut être supprimé que par son auteur, et une
+        // pierre tombale ne peut pas être remplacée par celle d'un autre
+        if feed.get(&delete.post_id).is_some_and(|post| !delete.applies_to(post)) {
+            return Ingest::Invalid;
+        }
+        if feed.deleted_by(&delete.post_id).is_some_and(|author| author != delete.author) {
+            return Ingest::Invalid;
+        }
+        if !feed.delete(&delete, self.max_posts) {
+            return Ingest::Duplicate;
+        }
+        if let Err(e) = self.store.append_tombstone(&delete) {
+            error!("❌ Erreur sauvegarde de la suppression de {}: {}", delete.post_id, e);
+        }
+        // Les posts sont réécrits d'abord : une pierre tombale oubliée ne
+        // doit pas laisser réapparaître son post au redémarrage
+        if self.store.should_compact_tombstones(feed.tombstone_count()) {
+            if let Err(e) = self.store.compact(feed.all()) {
+                error!("❌ Erreur compaction du stockage: {}", e);
+            }
+            if let Err(e) = self.store.compact_tombstones(&feed.tombstones()) {
+                error!("❌ Erreur compaction des suppressions: {}", e);
+            }
+        }
+        drop(feed);
+        self.reactions.write().await.retain_posts(|post_id| post_id != delete.post_id);
+
+        let _ = self.ws_broadcast.send(ServerFrame::PostDeleted { post_id: delete.post_id });
+        Ingest::Added
+    }
+
+    /// Abonnement (`following`) ou désabonnement signé par l'identité du nœud local
+    pub fn new_local_follow(&self, followee: String, following: bool) -> Follow {
+        Follow::new(followee, following, chrono::Utc::now().timestamp_millis(), &self.local_secret)
+    }
+
+    /// Point d'entrée unique des abonnements : vérification, dernier message
+    /// gagnant, journal puis diffusion de la nouvelle liste de l'abonné
+    pub async fn ingest_follow(&self, follow: &Follow, following: bool) -> Ingest {
+        if !follow.verify(following) {
+            return Ingest::Invalid;
+        }
+
+        let mut follows = self.follows.write().await;
+        if !follows.apply(follow, following) {
+            return Ingest::Duplicate;
+        }
+        if let Err(e) = self.store.append_follow(&follows::follow_message(follow.clone(), following)) {
+            error!("❌ Erreur sauvegarde de l'abonnement de {}: {}", follow.follower, e);
+        }
+        if let Err(e) = self.store.compact_follows(&follows.messages()) {
+            error!("❌ Erreur compaction des abonnements: {}", e);
+        }
+        let list = follows.following(&follow.follower);
+        drop(follows);
+
+        let frame = ServerFrame::Following { identity: follow.follower.clone(), following: list };
+        let _ = self.ws_broadcast.send(frame);
+        Ingest::Added
+    }
+
+    /// Message direct chiffré par l'identité du nœud local
+    pub fn new_local_direct(&self, recipient: String, content: &str) -> Option<DirectMessage> {
+        DirectMessage::seal(&self.local_secret, recipient, content, chrono::Utc::now().timestamp())
+    }
+
+    /// Déchiffre un message direct envoyé ou reçu par le nœud local
+    pub fn open_direct(&self, message: &DirectMessage) -> Option<String> {
+        message.open(&self.local_secret)
+    }
+
+    /// Point d'entrée unique des messages directs : vérification de
+    /// l'expéditeur, déduplication puis remise aux navigateurs concernés
+    pub async fn ingest_direct(&self, message: &DirectMessage) -> Ingest {
+        if !message.verify() {
+            return Ingest::Invalid;
+        }
+        if !self.directs.write().await.add(message) {
+            return Ingest::Duplicate;
+        }
+
+        if message.recipient == self.local_peer_id.to_string() {
+            // Le texte reste hors des logs, il se lit via /api/direct
+            match self.open_direct(message) {
+                Some(_) => info!("💌 Message direct reçu de {}", message.sender),
+                None => warn!("⚠️  Message direct de {} indéchiffrable", message.sender),
+            }
+        }
+
+        let _ = self.ws_broadcast.send(ServerFrame::Direct { message: message.clone() });
+        Ingest::Added
+    }
+
+    /// Profil signé par l'identité du nœud local
+    pub fn new_local_profile(&self, name: String, bio: String, avatar: Option<String>) -> Profile {
+        Profile::new(name, bio, avatar, chrono::Utc::now().timestamp_millis(), &self.local_secret)
+    }
+
+    /// Profil du nœud local ; le nom configuré remplace celui du profil
+    /// enregistré, et un profil est créé au premier démarrage
+    pub async fn init_local_profile(&self, configured_name: Option<&str>) -> Profile {
+        let local = self.local_peer_id.to_string();
+        let current = self.profiles.read().await.get(&local).cloned();
+        let profile = match current {
+            Some(profile) if configured_name.is_none_or(|name| name == profile.name) => return profile,
+            Some(profile) => self.new_local_profile(self.local_name.clone(), profile.bio, profile.avatar),
+            None => self.new_local_profile(self.local_name.clone(), String::new(), None),
+        };
+        if self.ingest_profile(&profile).await != Ingest::Added {
+            warn!("⚠️  Profil local invalide, nom trop long ?");
+        }
+        profile
+    }
+
+    /// Point d'entrée unique des profils : vérification, dernier profil
+    /// gagnant, journal puis diffusion aux navigateurs
+    pub async fn ingest_profile(&self, profile: &Profile) -> Ingest {
+        if !profile.verify() {
+            return Ingest::Invalid;
+        }
+
+        let mut profiles = self.profiles.write().await;
+        if !profiles.apply(profile) {
+            return Ingest::Duplicate;
+        }
+        if let Err(e) = self.store.append_profile(profile) {
+            error!("❌ Erreur sauvegarde du profil de {}: {}", profile.identity, e);
+        }
+        if let Err(e) = self.store.compact_profiles(&profiles.all()) {
+            error!("❌ Erreur compaction des profils: {}", e);
+        }
+        drop(profiles);
+
+        if let Some(peer) = self.peers.write().await.get_mut(&profile.identity) {
+            peer.name = Some(profile.name.clone());
+        }
+        let _ = self.ws_broadcast.send(ServerFrame::Profile { profile: profile.clone() });
+        Ingest::Added
+    }
+
+    /// Profils des auteurs d'une liste de posts
+    pub async fn author_profiles(&self, posts: &[Post]) -> Vec<Profile> {
+        self.profiles.read().await.for_identities(posts.iter().map(|p| p.author.as_str()))
+    }
+
+    /// Suit un canal pour le nœud, retourne `false` s'il l'était déjà
+    pub async fn join_channel(&self, name: &str) -> bool {
+        let joined = self.channels.write().await.join(name);
+        self.channels_changed.notify_one();
+        joined
+    }
+
+    /// Ne suit plus un canal pour le nœud, retourne `false` s'il ne l'était pas
+    pub async fn leave_channel(&self, name: &str) -> bool {
+        let left = self.channels.write().await.leave(name);
+        self.channels_changed.notify_one();
+        left
+    }
+
+    /// Une connexion WebSocket suit un canal : le nœud doit en recevoir les
+    /// posts. Retourne `false` si les navigateurs suivent déjà trop de canaux.
+    pub async fn browser_join_channel(&self, name: &str) -> bool {
+        let joined = self.channels.write().await.add_browser(name);
+        if joined {
+            self.channels_changed.notify_one();
+        }
+        joined
+    }
+
+    /// Une connexion WebSocket ne suit plus un canal ; au départ de la
+    /// dernière, la boucle du swarm se désabonne du topic
+    pub async fn browser_leave_channel(&self, name: &str) {
+        if self.channels.write().await.remove_browser(name) {
+            self.channels_changed.notify_one();
+        }
+    }
+
+    /// Posts d'un canal, du plus récent au plus ancien
+    pub async fn channel_posts(&self, name: &str) -> Vec<Post> {
+        self.posts.read().await.all().iter().filter(|p| p.channel() == name).cloned().collect()
+    }
+
+    /// Indique si un post appartient au fil personnalisé d'une identité
+    pub async fn in_home_feed(&self, identity: &str, post: &Post) -> bool {
+        post.author == identity || self.follows.read().await.is_following(identity, &post.author)
+    }
+
+    /// Posts des auteurs suivis par une identité, et les siens, du plus récent au plus ancien
+    pub async fn home_feed(&self, identity: &str) -> Vec<Post> {
+        let follows = self.follows.read().await;
+        self.posts
+            .read()
+            .await
+            .all()
+            .iter()
+            .filter(|post| post.author == identity || follows.is_following(identity, &post.author))
+            .cloned()
+            .collect()
+    }
+
+    /// Un post et l'arbre de ses réponses
+    pub async fn thread(&self, id: &str) -> Option<zeta_proto::ThreadNode> {
+        self.posts.read().await.thread(id)
+    }
+
+    /// Fusionne des profils reçus par synchronisation
+    pub async fn merge_profiles(&self, incoming: Vec<Profile>) {
+        for profile in incoming {
+            if self.ingest_profile(&profile).await == Ingest::Invalid {
+                warn!("🚫 Profil synchronisé à signature invalide ignoré ({})", profile.identity);
+            }
+        }
+    }
+
+    /// Fusionne une réponse de synchronisation, retourne le nombre de nouveaux posts
+    ///
+    /// Les nouveaux posts et compteurs de réactions partent en une seule
+    /// trame : une réponse de synchronisation dépasse la capacité du canal de
+    /// diffusion.
+    pub async fn merge_sync(&self, response: SyncResponse) -> usize {
+        self.merge_profiles(response.profiles).await;
+
+        let mut added = Vec::new();
+        for post in response.posts {
+            match self.store_post(&post).await {
+                Ingest::Added => added.push(post),
+                Ingest::Duplicate => {}
+                Ingest::Invalid => warn!("🚫 Post synchronisé à signature invalide ignoré"),
+            }
+        }
+
+        let mut touched = BTreeSet::new();
+        for reaction in response.reactions.iter().take(sync::SYNC_MAX_REACTIONS) {
+            match self.store_reaction(reaction).await {
+                Ingest::Added => {
+                    touched.insert(reaction.post_id.as_str());
+                }
+                Ingest::Duplicate => {}
+                Ingest::Invalid => warn!("🚫 Réaction synchronisée à signature invalide ignorée ({})", reaction.author),
+            }
+        }
+
+        let count = added.len();
+        if !added.is_empty() || !touched.is_empty() {
+            added.sort_by_key(|p| std::cmp::Reverse(p.timestamp));
+            let counts = self.reactions.read().await;
+            let reactions = touched.into_iter().map(|id| (id.to_string(), counts.counts(id))).collect();
+            drop(counts);
+            let _ = self.ws_broadcast.send(ServerFrame::NewPosts { posts: added, reactions });
+        }
+        count
+    }
+
+    /// Répond à une requête de synchronisation
+    pub async fn answer_sync(&self, request: SyncRequest) -> SyncResponse {
+        let feed = self.posts.read().await;
+        let posts: Vec<Post> = match request {
+            SyncRequest::Since { timestamp, limit } => feed
+                .all()
+                .iter()
+                .filter(|p| p.timestamp >= timestamp)
+                .take(limit.min(sync::SYNC_MAX_POSTS))
+                .cloned()
+                .collect(),
+            SyncRequest::Ids { ids } => ids
+                .iter()
+                .filter_map(|id| feed.get(id))
+                .take(sync::SYNC_MAX_POSTS)
+                .cloned()
+                .collect(),
+        };
+        drop(feed);
+        // Le profil du nœud accompagne ceux des auteurs, même sans post
+        let local = self.local_peer_id.to_string();
+        let profiles = self
+            .profiles
+            .read()
+            .await
+            .for_identities(posts.iter().map(|p| p.author.as_str()).chain([local.as_str()]));
+        // Les réactions accompagnent les posts envoyés
+        let reactions = self
+            .reactions
+            .read()
+            .await
+            .for_posts(posts.iter().map(|p| p.id.as_str()))
+            .take(sync::SYNC_MAX_REACTIONS)
+            .collect();
+        SyncResponse { posts, profiles, reactions }
+    }
+
+    /// Requête de rattrapage à envoyer à un nouveau pair
+    pub async fn sync_request(&self) -> SyncRequest {
+        let newest = self.posts.read().await.all().first().map(|p| p.timestamp).unwrap_or(0);
+        SyncRequest::Since {
+            timestamp: newest.saturating_sub(sync::SYNC_OVERLAP_SECS).max(0),
+            limit: sync::SYNC_MAX_POSTS,
+        }
+    }
+
+    /// Enregistre ou rafraîchit un relay annoncé sur le réseau
+    pub async fn record_relay(&self, peer_id: String, ws_url: String) {
+        let info = RelayInfo {
+            peer_id: peer_id.clone(),
+            ws_url,
+            last_seen: chrono::Utc::now().timestamp(),
+        };
+        self.relays.write().await.insert(peer_id, info);
+    }
+
+    /// Relais annoncés récemment, du plus récent au plus ancien
+    pub async fn known_relays(&self) -> Vec<RelayInfo> {
+        let cutoff = chrono::Utc::now().timestamp() - discovery::RELAY_EXPIRY_SECS;
+        let mut relays = self.relays.write().await;
+        relays.retain(|_, r| r.last_seen >= cutoff);
+        let mut list: Vec<_> = relays.values().cloned().collect();
+        list.sort_by_key(|r| std::cmp::Reverse(r.last_seen));
+        list
+    }
+
+    pub async fn broadcast_to_ws(&self, frame: ServerFrame) {
+        let _ = self.ws_broadcast.send(frame);
     }
 }
 
@@ -123,45 +802,37 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .with_thread_ids(false)
         .init();
 
+    let cli = config::Cli::parse();
+    let config = config::Config::load(cli.options)?;
+
+    match cli.command.unwrap_or(config::Command::Run) {
+        config::Command::Config => {
+            print!("{}", toml::to_string_pretty(&config)?);
+            return Ok(());
+        }
+        config::Command::Identity => {
+            let key = load_or_generate_identity(&config.identity_file)?;
+            println!("{}", PeerId::from(key.public()));
+            return Ok(());
+        }
+        config::Command::Run => {}
+    }
+
     info!("🚀 Démarrage de Zeta2 - Réseau social décentralisé");
 
-    let args: Vec<String> = std::env::args().collect();
-    let is_relay = args.contains(&"--relay".to_string()) || args.contains(&"--server".to_string());
-    let relay_addr: Option<String> = args.iter()
-        .position(|x| x == "--relay-addr")
-        .and_then(|i| args.get(i + 1))
-        .cloned();
-    let username: Option<String> = args.iter()
-        .position(|x| x == "--name")
-        .and_then(|i| args.get(i + 1))
-        .cloned();
-    let web_port: u16 = args.iter()
-        .position(|x| x == "--web-port")
-        .and_then(|i| args.get(i + 1))
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(3030);
+    let is_relay = config.relay;
+    let relay_addr = config.relay_addr.clone();
+    let web_port = config.web_port;
 
     info!("⚙️  Mode: {}", if is_relay { "RELAY (Serveur)" } else { "CLIENT" });
 
     // Charger ou générer les clés
-    let key_file = "identity.key";
-    let local_key = if Path::new(key_file).exists() {
-        info!("🔐 Chargement des clés existantes...");
-        let key_bytes = fs::read(key_file)?;
-        Keypair::from_protobuf_encoding(&key_bytes)?
-    } else {
-        info!("🔑 Génération de nouvelles clés...");
-        let key = Keypair::generate_ed25519();
-        let key_bytes = key.to_protobuf_encoding()?;
-        fs::write(key_file, key_bytes)?;
-        info!("💾 Clés sauvegardées dans {}", key_file);
-        key
-    };
+    let local_key = load_or_generate_identity(&config.identity_file)?;
     
     let local_peer_id = PeerId::from(local_key.public());
     info!("🔑 Peer ID: {}", local_peer_id);
     
-    let local_name = username.unwrap_or_else(|| format!("Peer-{}", &local_peer_id.to_string()[..8]));
+    let local_name = config.name.clone().unwrap_or_else(|| format!("Peer-{}", &local_peer_id.to_string()[..8]));
     info!("👤 Nom: {}", local_name);
 
     info!("📝 Initialisation du swarm...");
@@ -174,11 +845,14 @@ async fn main() -> Result<(), Box<dyn Error>> {
             noise::Config::new,
             yamux::Config::default,
         )?
-        .with_behaviour(|key| {
+        .with_relay_client(noise::Config::new, yamux::Config::default)?
+        .with_behaviour(|key, relay_client| {
             info!("📝 Configuration Gossipsub...");
             let gossipsub_config = gossipsub::ConfigBuilder::default()
                 .heartbeat_interval(Duration::from_secs(10))
-                .validation_mode(gossipsub::ValidationMode::Permissive)
+                .validation_mode(gossipsub::ValidationMode::Strict)
+                // Un message n'est relayé qu'après `report_message_validation_result`
+                .validate_messages()
                 .build()
                 .expect("Configuration Gossipsub valide");
 
@@ -188,17 +862,25 @@ async fn main() -> Result<(), Box<dyn Error>> {
             )
             .expect("Impossible de créer Gossipsub");
 
-            let topic = IdentTopic::new("zeta2-social");
+            let topic = IdentTopic::new(&config.topic);
             gossipsub.subscribe(&topic).unwrap();
 
+            if config.peer_scoring.enabled {
+                info!("📝 Configuration du score des pairs...");
+                let (mut params, thresholds) = scoring::params(&config.peer_scoring);
+                params.topics.insert(topic.hash(), scoring::topic_params(&config.peer_scoring));
+                gossipsub.with_peer_score(params, thresholds)
+                    .map_err(|e| format!("score des pairs invalide: {}", e))?;
+            }
+
             info!("📝 Configuration Identify...");
             let identify = identify::Behaviour::new(identify::Config::new(
-                "/zeta2/1.0.0".to_string(),
+                envelope::IDENTIFY_PROTOCOL_VERSION.to_string(),
                 key.public(),
             ).with_push_listen_addr_updates(true));
 
             info!("📝 Configuration Kademlia...");
-            let kad = kad::Behaviour::new(local_peer_id, MemoryStore::new(local_peer_id));
+            let kad = discovery::new_behaviour(local_peer_id, is_relay);
 
             info!("📝 Configuration mDNS...");
             let mdns = mdns::Behaviour::new(mdns::Config::default(), local_peer_id)
@@ -207,30 +889,46 @@ async fn main() -> Result<(), Box<dyn Error>> {
             info!("📝 Configuration Ping...");
             let ping = ping::Behaviour::new(ping::Config::new().with_interval(Duration::from_secs(15)));
 
+            info!("📝 Configuration Sync ({})...", sync::SYNC_PROTOCOL);
+            let sync = sync::new_behaviour();
+
+            info!("📝 Configuration échange de blocs ({})...", blocks::BLOCKS_PROTOCOL);
+            let blocks = blocks::new_behaviour();
+
+            info!("📝 Configuration Circuit Relay v2 + DCUtR...");
+            let relay_server = is_relay.then(|| relay::Behaviour::new(local_peer_id, relay::Config::default()));
+            let dcutr = dcutr::Behaviour::new(local_peer_id);
+
             Ok(ZetaBehaviour {
                 gossipsub,
                 mdns,
                 ping,
                 identify,
                 kad,
+                sync,
+                blocks,
+                relay_server: Toggle::from(relay_server),
+                relay_client,
+                dcutr,
             })
         })?
         .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
         .build();
 
     info!("✅ Swarm créé avec succès");
-    let topic = IdentTopic::new("zeta2-social");
+    let topic = IdentTopic::new(&config.topic);
     info!("📡 Abonné au topic: {}", topic);
 
     // Configurer les listeners
     info!("📝 Configuration des listeners...");
     
+    let p2p_port = config.p2p_port();
     if is_relay {
-        info!("🖥️  Mode RELAY - Écoute TCP sur 0.0.0.0:4001");
-        swarm.listen_on("/ip4/0.0.0.0/tcp/4001".parse()?)?;
+        info!("🖥️  Mode RELAY - Écoute TCP sur 0.0.0.0:{}", p2p_port);
+        swarm.listen_on(format!("/ip4/0.0.0.0/tcp/{}", p2p_port).parse()?)?;
     } else {
-        info!("💻 Mode CLIENT - Ports aléatoires");
-        swarm.listen_on("/ip4/0.0.0.0/tcp/0".parse()?)?;
+        info!("💻 Mode CLIENT - Port {}", if p2p_port == 0 { "aléatoire".to_string() } else { p2p_port.to_string() });
+        swarm.listen_on(format!("/ip4/0.0.0.0/tcp/{}", p2p_port).parse()?)?;
 
         if let Some(ref addr) = relay_addr {
             if let Ok(relay_multiaddr) = addr.parse::<Multiaddr>() {
@@ -240,18 +938,46 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    let network_state = NetworkState::new(local_peer_id, local_name.clone());
+    let store: Arc<dyn PostStore> = if config.in_memory {
+        info!("💾 Stockage des posts: mémoire uniquement");
+        Arc::new(MemoryPostStore)
+    } else {
+        info!("💾 Stockage des posts: {} (max {})", config.posts_file.display(), config.max_posts);
+        Arc::new(JsonlPostStore::open(&config.posts_file))
+    };
+    let blobs = if config.in_memory {
+        BlobStore::in_memory(config.blob_quota())
+    } else {
+        info!("📎 Fichiers joints: {} (quota {} Mio)", config.blobs_dir.display(), config.blob_quota_mb);
+        BlobStore::open(&config.blobs_dir, config.blob_quota())?
+    };
+    let network_state = NetworkState::new(
+        local_key.clone(),
+        local_name.clone(),
+        store,
+        Arc::new(blobs),
+        Arc::new(RateLimiter::new(config.rate_limits(), config.trusted_proxies.clone())),
+        config.max_posts,
+        config.channels.clone(),
+    );
+    let profile = network_state.init_local_profile(config.name.as_deref()).await;
+    info!("🪪 Profil: {}", profile.name);
+    // Abonnements gossipsub initiaux aux canaux configurés
+    network_state.channels_changed.notify_one();
+    let mut channel_topics: BTreeSet<String> = BTreeSet::from([channel::DEFAULT_CHANNEL.to_string()]);
 
     let relay_multiaddr: Option<Multiaddr> = relay_addr.as_ref().and_then(|a| a.parse().ok());
     let relay_peer_id: Option<PeerId> = relay_multiaddr.as_ref().and_then(|addr| {
         addr.iter().find_map(|p| {
-            if let libp2p::multiaddr::Protocol::P2p(peer_id) = p {
+            if let Protocol::P2p(peer_id) = p {
                 Some(peer_id)
             } else {
                 None
             }
         })
     });
+    // Réservation de circuit active auprès du relay
+    let mut circuit_listener = None;
 
     // Channel pour les posts (depuis web vers P2P)
     let (post_tx, mut post_rx) = mpsc::unbounded_channel::<Post>();
@@ -261,17 +987,28 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Démarrer le serveur web avec WebSocket
     let web_state = network_state.clone();
     let web_name = local_name.clone();
+    let static_dir = config.static_dir.clone();
     tokio::spawn(async move {
-        if let Err(e) = web_server::start_server(web_state, post_tx, ws_to_p2p_tx, web_name, is_relay, web_port).await {
+        if let Err(e) = web_server::start_server(web_state, post_tx, ws_to_p2p_tx, web_name, is_relay, web_port, static_dir).await {
             error!("❌ Erreur serveur web: {}", e);
         }
     });
 
+    // Nettoyage des seaux de limitation, hors du chemin des publications
+    let limits = network_state.limits.clone();
+    tokio::spawn(async move {
+        let mut prune_interval = tokio::time::interval(ratelimit::PRUNE_INTERVAL);
+        loop {
+            prune_interval.tick().await;
+            limits.prune();
+        }
+    });
+
     info!("🎉 Zeta2 démarré! Interface web sur http://localhost:{}", web_port);
     info!("⏳ En attente des événements réseau...");
 
     // Charger les bootstrap peers (autres relais connus)
-    let bootstrap_peers = load_bootstrap_peers();
+    let bootstrap_peers = load_bootstrap_peers(&config.bootstrap_file);
     if !bootstrap_peers.is_empty() {
         info!("📋 {} bootstrap peer(s) trouvé(s)", bootstrap_peers.len());
         for (peer_id, addr) in &bootstrap_peers {
@@ -284,18 +1021,58 @@ async fn main() -> Result<(), Box<dyn Error>> {
             }
         }
     } else {
-        info!("📋 Aucun bootstrap peer configuré (fichier bootstrap.txt)");
+        info!("📋 Aucun bootstrap peer configuré (fichier {})", config.bootstrap_file.display());
     }
 
+    // Découverte Kademlia : bootstrap et annonce des relais, puis marches aléatoires
+    let mut bootstrap_interval = tokio::time::interval(discovery::BOOTSTRAP_INTERVAL);
+    let mut discovery_interval = tokio::time::interval(discovery::DISCOVERY_INTERVAL);
+    discovery_interval.tick().await;
+    let mut announce_interval = tokio::time::interval(discovery::RELAY_ANNOUNCE_INTERVAL);
+
     // Timer pour reconnexion automatique (commence après 30s)
     let mut reconnect_interval = tokio::time::interval(Duration::from_secs(30));
     reconnect_interval.tick().await; // Consommer le premier tick immédiat
+    // Relevé des scores gossipsub pour /api/network
+    let mut score_interval = tokio::time::interval(Duration::from_secs(10));
+    let topic_score_params = config.peer_scoring.enabled.then(|| scoring::topic_params(&config.peer_scoring));
     let mut connected_to_relay = false;
+    // Demandes de blocs en cours : requête -> CID
+    let mut block_requests: HashMap<request_response::OutboundRequestId, String> = HashMap::new();
     let bootstrap_peers_clone = bootstrap_peers.clone();
 
     // Boucle événements
     loop {
         tokio::select! {
+            // Bootstrap Kademlia périodique
+            _ = bootstrap_interval.tick() => {
+                if let Err(e) = swarm.behaviour_mut().kad.bootstrap() {
+                    info!("🗺️  Bootstrap Kademlia impossible: {:?}", e);
+                }
+                if is_relay {
+                    if let Err(e) = swarm.behaviour_mut().kad.start_providing(discovery::relay_key()) {
+                        warn!("⚠️  Annonce du relay dans le DHT impossible: {}", e);
+                    }
+                }
+            }
+            // Marche aléatoire et recherche de relais
+            _ = discovery_interval.tick() => {
+                swarm.behaviour_mut().kad.get_closest_peers(PeerId::random());
+                swarm.behaviour_mut().kad.get_providers(discovery::relay_key());
+            }
+            // Annonce de l'URL WebSocket publique du relay
+            _ = announce_interval.tick(), if is_relay => {
+                if let Some(ws_url) = discovery::public_ws_url(config.public_ws_url.as_deref()) {
+                    let msg = NetworkMessage::RelayAnnounce {
+                        peer_id: local_peer_id.to_string(),
+                        ws_url,
+                    };
+                    let data = envelope::encode(&msg);
+                    if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic.clone(), data) {
+                        info!("📣 Annonce du relay non publiée: {}", e);
+                    }
+                }
+            }
             // Timer de reconnexion
             _ = reconnect_interval.tick() => {
                 // Reconnecter au relay si configuré
@@ -318,37 +1095,77 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     }
                 }
             }
-            // Message depuis WebSocket client vers P2P
+            // Message depuis WebSocket client vers P2P (déjà ingéré par le serveur web)
             Some(ws_msg) = ws_to_p2p_rx.recv() => {
-                if let Ok(json) = serde_json::to_vec(&ws_msg) {
-                    if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic.clone(), json) {
-                        error!("❌ Erreur publication WS->P2P: {}", e);
-                    } else {
-                        info!("📤 Message WebSocket relayé au réseau P2P");
-                        // Si c'est un post, l'ajouter localement aussi
-                        if let NetworkMessage::Post(post) = ws_msg {
-                            network_state.add_post(post).await;
-                        }
+                // Ne pas relayer ce que les pairs rejetteraient : notre score en pâtirait
+                if let Err(rejection) = validation::check(&ws_msg, None, chrono::Utc::now().timestamp()) {
+                    warn!("🚫 Message WebSocket non relayé: {}", rejection);
+                    continue;
+                }
+                let data = envelope::encode(&ws_msg);
+                if let Err(e) = swarm.behaviour_mut().gossipsub.publish(gossip_topic(&config.topic, &ws_msg), data) {
+                    error!("❌ Erreur publication WS->P2P: {}", e);
+                } else {
+                    info!("📤 Message WebSocket relayé au réseau P2P");
+                }
+            }
+            // Scores des pairs : relevé pour l'API, les plus mal notés signalés
+            _ = score_interval.tick(), if config.peer_scoring.enabled => {
+                let gossipsub = &swarm.behaviour().gossipsub;
+                let scores: HashMap<String, f64> = gossipsub
+                    .all_peers()
+                    .filter_map(|(peer, _)| gossipsub.peer_score(peer).map(|score| (peer.to_string(), score)))
+                    .collect();
+                for (peer, score) in &scores {
+                    if *score < config.peer_scoring.graylist_threshold {
+                        warn!("📉 Pair {} sous le seuil de mise à l'écart (score {:.1})", peer, score);
+                    }
+                }
+                *network_state.peer_scores.write().await = scores;
+            }
+            // Canaux suivis modifiés (configuration, REST ou WebSocket)
+            _ = network_state.channels_changed.notified() => {
+                let wanted = network_state.channels.read().await.wanted();
+                sync_channel_topics(&mut swarm, &config.topic, &wanted, &mut channel_topics, topic_score_params.as_ref());
+            }
+            // Fichiers joints manquants : les demander à quelques pairs
+            _ = network_state.blobs_wanted.notified() => {
+                let pending: BTreeSet<String> = block_requests.values().cloned().collect();
+                let wanted: Vec<String> = network_state.wanted_blobs.read().await.difference(&pending).cloned().collect();
+                let peers: Vec<PeerId> = swarm.connected_peers().take(blocks::BLOCK_FANOUT).cloned().collect();
+                for cid in &wanted {
+                    if peers.is_empty() {
+                        network_state.forget_blob(cid).await;
+                        continue;
                     }
+                    for peer in &peers {
+                        let request_id = swarm.behaviour_mut().blocks.send_request(peer, BlockRequest { cid: cid.clone() });
+                        block_requests.insert(request_id, cid.clone());
+                    }
+                    debug!("📎 Bloc {} demandé à {} pair(s)", cid, peers.len());
                 }
             }
             // Post depuis l'interface locale
             Some(post) = post_rx.recv() => {
+                if network_state.ingest_post(post.clone()).await != Ingest::Added {
+                    warn!("⚠️  Post local ignoré (doublon ou invalide): {}", post.id);
+                    continue;
+                }
                 let msg = NetworkMessage::Post(post.clone());
-                if let Ok(json) = serde_json::to_vec(&msg) {
-                    if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic.clone(), json) {
-                        error!("❌ Erreur publication: {}", e);
-                    } else {
-                        info!("📤 Post publié: {}", post.content);
-                        network_state.add_post(post).await;
-                    }
+                let data = envelope::encode(&msg);
+                if let Err(e) = swarm.behaviour_mut().gossipsub.publish(gossip_topic(&config.topic, &msg), data) {
+                    error!("❌ Erreur publication: {}", e);
+                } else {
+                    info!("📤 Post publié: {}", post.content);
                 }
             }
             event = swarm.select_next_some() => match event {
                 SwarmEvent::NewListenAddr { address, .. } => {
                     let full_addr = format!("{}/p2p/{}", address, local_peer_id);
                     info!("🎧 Écoute sur: {}", full_addr);
-                    if is_relay {
+                    if is_relay && !address.iter().any(|p| matches!(p, Protocol::P2pCircuit)) {
+                        // Les réservations de circuit annoncent les adresses externes du relay
+                        swarm.add_external_address(address.clone());
                         info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
                         info!("📋 ADRESSE BOOTSTRAP À PARTAGER:");
                         info!("   {}", full_addr);
@@ -357,36 +1174,141 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 }
                 SwarmEvent::Behaviour(ZetaBehaviourEvent::Gossipsub(
                     gossipsub::Event::Message {
-                        propagation_source: _peer_id,
+                        propagation_source,
+                        message_id,
                         message,
-                        ..
                     },
                 )) => {
-                    if let Ok(msg) = serde_json::from_slice::<NetworkMessage>(&message.data) {
-                        match msg {
+                    let source = message.source;
+                    let verdict = validation::validate(
+                        &message.data,
+                        source.map(|p| p.to_string()).as_deref(),
+                        chrono::Utc::now().timestamp(),
+                    );
+                    let limited = gossip_limit_key(&verdict, &propagation_source)
+                        .and_then(|key| network_state.limits.check(&[(LimitScope::Peer, &key)]).err());
+                    let acceptance = gossip_acceptance(&verdict, limited.is_some());
+                    if let Err(e) = swarm.behaviour_mut().gossipsub.report_message_validation_result(&message_id, &propagation_source, acceptance) {
+                        debug!("⚠️  Verdict de validation non transmis: {}", e);
+                    }
+                    if let Some(limited) = limited {
+                        debug!("⏳ Message de {:?} ignoré, débit dépassé (prochain dans {:?})", source, limited.retry_after);
+                        continue;
+                    }
+                    match verdict {
+                        Ok(Decoded::Message(msg)) => match *msg {
                             NetworkMessage::Post(post) => {
-                                info!("📨 Nouveau post de {}: {}", post.author_name, post.content);
-                                network_state.add_post(post).await;
+                                let (id, author) = (post.id.clone(), post.author.clone());
+                                let summary = format!("{}: {}", post.author_name, post.content);
+                                match network_state.ingest_post(post).await {
+                                    Ingest::Added => info!("📨 Nouveau post de {}", summary),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Post à signature invalide rejeté: {} ({})", id, author),
+                                }
                             }
                             NetworkMessage::PeerJoined { peer_id, name } => {
                                 info!("👤 Peer {} ({}) a rejoint", name, peer_id);
+                                // Joindre le pair via le circuit du relay, DCUtR tentera ensuite une connexion directe
+                                if let (Some(relay_addr), Ok(remote)) = (&relay_multiaddr, peer_id.parse::<PeerId>()) {
+                                    if remote != local_peer_id && !swarm.is_connected(&remote) {
+                                        let circuit_addr = relay_addr.clone()
+                                            .with(Protocol::P2pCircuit)
+                                            .with(Protocol::P2p(remote));
+                                        info!("🔀 Connexion via circuit relay: {}", circuit_addr);
+                                        if let Err(e) = swarm.dial(circuit_addr) {
+                                            warn!("⚠️  Échec connexion via circuit: {}", e);
+                                        }
+                                    }
+                                }
                             }
                             NetworkMessage::PeerLeft { peer_id } => {
                                 info!("👋 Peer {} a quitté", peer_id);
                             }
+                            NetworkMessage::RelayAnnounce { peer_id, ws_url } => {
+                                info!("📣 Relay {} disponible sur {}", peer_id, ws_url);
+                                network_state.record_relay(peer_id, ws_url).await;
+                            }
+                            NetworkMessage::Reaction(reaction) => {
+                                match network_state.ingest_reaction(&reaction).await {
+                                    Ingest::Added => info!("{} Réaction de {} au post {}", reaction.emoji, reaction.author, reaction.post_id),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Réaction à signature invalide rejetée ({})", reaction.author),
+                                }
+                            }
+                            NetworkMessage::Edit(edit) => {
+                                let (id, author) = (edit.post_id.clone(), edit.author.clone());
+                                match network_state.ingest_edit(edit).await {
+                                    Ingest::Added => info!("✏️  Post {} modifié par {}", id, author),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Modification à signature invalide rejetée: {} ({})", id, author),
+                                }
+                            }
+                            NetworkMessage::Delete(delete) => {
+                                let (id, author) = (delete.post_id.clone(), delete.author.clone());
+                                match network_state.ingest_delete(delete).await {
+                                    Ingest::Added => info!("🗑️  Post {} supprimé par {}", id, author),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Suppression non autorisée rejetée: {} ({})", id, author),
+                                }
+                            }
+                            NetworkMessage::Follow(follow) => {
+                                match network_state.ingest_follow(&follow, true).await {
+                                    Ingest::Added => info!("➕ {} suit {}", follow.follower, follow.followee),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Abonnement à signature invalide rejeté ({})", follow.follower),
+                                }
+                            }
+                            NetworkMessage::Unfollow(follow) => {
+                                match network_state.ingest_follow(&follow, false).await {
+                                    Ingest::Added => info!("➖ {} ne suit plus {}", follow.follower, follow.followee),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Désabonnement à signature invalide rejeté ({})", follow.follower),
+                                }
+                            }
+                            NetworkMessage::Direct(message) => {
+                                match network_state.ingest_direct(&message).await {
+                                    Ingest::Added => debug!("✉️  Message direct de {} pour {}", message.sender, message.recipient),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Message direct à signature invalide rejeté ({})", message.sender),
+                                }
+                            }
+                            NetworkMessage::Profile(profile) => {
+                                match network_state.ingest_profile(&profile).await {
+                                    Ingest::Added => info!("🪪 Profil de {} mis à jour: {}", profile.identity, profile.name),
+                                    Ingest::Duplicate => {}
+                                    Ingest::Invalid => warn!("🚫 Profil invalide rejeté ({})", profile.identity),
+                                }
+                            }
                             NetworkMessage::Heartbeat => {}
+                        },
+                        Ok(Decoded::Unknown { version, kind }) => {
+                            // Accepté donc relayé, on ne l'interprète simplement pas
+                            network_state.message_stats.unknown.fetch_add(1, Ordering::Relaxed);
+                            debug!("❔ Message {} (v{}) inconnu, relayé sans traitement", kind, version);
+                        }
+                        Err(rejection) if rejection.is_malformed() => {
+                            network_state.message_stats.malformed.fetch_add(1, Ordering::Relaxed);
+                            warn!("⚠️  Message gossip illisible de {}: {}", propagation_source, rejection);
+                        }
+                        Err(rejection) => {
+                            network_state.message_stats.rejected.fetch_add(1, Ordering::Relaxed);
+                            warn!("🚫 Message gossip rejeté de {}: {}", propagation_source, rejection);
                         }
                     }
                 }
                 SwarmEvent::Behaviour(ZetaBehaviourEvent::Mdns(mdns::Event::Discovered(list))) => {
                     for (peer_id, multiaddr) in list {
                         info!("🔍 Peer découvert via mDNS: {}", peer_id);
-                        swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                        // Un pair explicite échappe au score : réservé au mode sans score
+                        if !config.peer_scoring.enabled {
+                            swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                        }
                         network_state.add_peer(PeerInfo {
                             peer_id: peer_id.to_string(),
                             address: multiaddr.to_string(),
                             name: None,
                             is_browser: false,
+                            public_key: None,
                         }).await;
                     }
                 }
@@ -402,20 +1324,163 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     ..
                 })) => {
                     info!("🆔 Peer identifié: {}", peer_id);
+                    // Alimenter la table de routage Kademlia avec les adresses annoncées
+                    if info.protocols.contains(&discovery::KAD_PROTOCOL) {
+                        for addr in &info.listen_addrs {
+                            swarm.behaviour_mut().kad.add_address(&peer_id, addr.clone());
+                        }
+                    }
+                    if is_relay {
+                        // Adresse observée par le pair : candidate pour les réservations de circuit
+                        swarm.add_external_address(info.observed_addr.clone());
+                    }
                     let addr = info.listen_addrs.first().map(|a| a.to_string()).unwrap_or_default();
                     network_state.add_peer(PeerInfo {
                         peer_id: peer_id.to_string(),
                         address: addr,
                         name: None,
                         is_browser: false,
+                        public_key: info.public_key.try_into_ed25519().ok().map(|k| hex::encode(k.to_bytes())),
                     }).await;
                 }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::Sync(request_response::Event::Message { peer, message })) => {
+                    match message {
+                        request_response::Message::Request { request, channel, .. } => {
+                            let response = network_state.answer_sync(request).await;
+                            info!("🔁 Sync: {} post(s) envoyé(s) à {}", response.posts.len(), peer);
+                            if swarm.behaviour_mut().sync.send_response(channel, response).is_err() {
+                                warn!("⚠️  Réponse de sync non envoyée à {}", peer);
+                            }
+                        }
+                        request_response::Message::Response { response, .. } => {
+                            let received = response.posts.len();
+                            let added = network_state.merge_sync(response).await;
+                            info!("🔁 Sync: {} nouveau(x) post(s) sur {} reçu(s) de {}", added, received, peer);
+                        }
+                    }
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::Sync(request_response::Event::OutboundFailure { peer, error, .. })) => {
+                    warn!("⚠️  Échec de sync avec {}: {}", peer, error);
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::Blocks(request_response::Event::Message { peer, message })) => {
+                    match message {
+                        request_response::Message::Request { request, channel, .. } => {
+                            let response = network_state.answer_block(request);
+                            if swarm.behaviour_mut().blocks.send_response(channel, response).is_err() {
+                                warn!("⚠️  Bloc non envoyé à {}", peer);
+                            }
+                        }
+                        request_response::Message::Response { request_id, response } => {
+                            let cid = block_requests.remove(&request_id).unwrap_or_default();
+                            match network_state.store_block(response).await {
+                                Ok(Some(attachment)) => info!("📎 Bloc {} ({}, {} octets) reçu de {}", attachment.cid, attachment.mime, attachment.size, peer),
+                                Ok(None) => {}
+                                Err(e) => warn!("⚠️  Bloc {} de {} refusé: {}", cid, peer, e),
+                            }
+                            if !block_requests.values().any(|c| *c == cid) {
+                                network_state.forget_blob(&cid).await;
+                            }
+                        }
+                    }
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::Blocks(request_response::Event::OutboundFailure { peer, request_id, error, .. })) => {
+                    if let Some(cid) = block_requests.remove(&request_id) {
+                        debug!("📎 Échec de la demande du bloc {} à {}: {}", cid, peer, error);
+                        if !block_requests.values().any(|c| *c == cid) {
+                            network_state.forget_blob(&cid).await;
+                        }
+                    }
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::Kad(event)) => {
+                    match event {
+                        kad::Event::RoutingUpdated { peer, is_new_peer: true, .. } => {
+                            info!("🗺️  Nouveau pair dans la table Kademlia: {}", peer);
+                        }
+                        kad::Event::OutboundQueryProgressed { result, .. } => match result {
+                            kad::QueryResult::GetProviders(Ok(kad::GetProvidersOk::FoundProviders { providers, .. })) => {
+                                for provider in providers {
+                                    if provider != local_peer_id && !swarm.is_connected(&provider) {
+                                        info!("🛰️  Relay découvert via DHT: {}", provider);
+                                        if let Err(e) = swarm.dial(provider) {
+                                            warn!("⚠️  Échec connexion au relay {}: {}", provider, e);
+                                        }
+                                    }
+                                }
+                            }
+                            kad::QueryResult::Bootstrap(Ok(kad::BootstrapOk { num_remaining: 0, .. })) => {
+                                info!("🗺️  Bootstrap Kademlia terminé");
+                            }
+                            kad::QueryResult::StartProviding(Ok(_)) => {
+                                info!("🛰️  Relay annoncé dans le DHT ({})", discovery::RELAY_PROVIDER_KEY);
+                            }
+                            _ => {}
+                        },
+                        _ => {}
+                    }
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::RelayServer(event)) => {
+                    match event {
+                        relay::Event::ReservationReqAccepted { src_peer_id, .. } => {
+                            info!("🛰️  Réservation de circuit acceptée pour {}", src_peer_id);
+                        }
+                        relay::Event::CircuitReqAccepted { src_peer_id, dst_peer_id } => {
+                            info!("🛰️  Circuit ouvert: {} -> {}", src_peer_id, dst_peer_id);
+                        }
+                        other => info!("🛰️  Relay: {:?}", other),
+                    }
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::RelayClient(event)) => {
+                    match event {
+                        relay::client::Event::ReservationReqAccepted { relay_peer_id, renewal, .. } => {
+                            info!("🛰️  Circuit réservé sur le relay {}", relay_peer_id);
+                            if !renewal {
+                                // Annoncer notre présence pour que les autres clients du relay nous joignent
+                                let msg = NetworkMessage::PeerJoined {
+                                    peer_id: local_peer_id.to_string(),
+                                    name: local_name.clone(),
+                                };
+                                let data = envelope::encode(&msg);
+                                if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic.clone(), data) {
+                                    warn!("⚠️  Annonce PeerJoined non publiée: {}", e);
+                                }
+                            }
+                        }
+                        relay::client::Event::OutboundCircuitEstablished { relay_peer_id, .. } => {
+                            info!("🛰️  Circuit sortant établi via {}", relay_peer_id);
+                        }
+                        relay::client::Event::InboundCircuitEstablished { src_peer_id, .. } => {
+                            info!("🛰️  Circuit entrant de {}", src_peer_id);
+                        }
+                    }
+                }
+                SwarmEvent::Behaviour(ZetaBehaviourEvent::Dcutr(dcutr::Event { remote_peer_id, result })) => {
+                    match result {
+                        Ok(_) => info!("🕳️  Hole punching réussi avec {}: connexion directe", remote_peer_id),
+                        Err(e) => warn!("⚠️  Hole punching échoué avec {}: {}", remote_peer_id, e),
+                    }
+                }
                 SwarmEvent::ConnectionEstablished { peer_id, num_established, .. } => {
                     info!("✅ Connexion: {} (total: {})", peer_id, num_established);
-                    swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                    if !config.peer_scoring.enabled {
+                        swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+                    }
+                    // Rattraper l'historique manqué auprès du nouveau pair
+                    if num_established.get() == 1 {
+                        let request = network_state.sync_request().await;
+                        swarm.behaviour_mut().sync.send_request(&peer_id, request);
+                    }
                     if Some(peer_id) == relay_peer_id {
                         connected_to_relay = true;
                         info!("🔗 Connecté au relay!");
+                        // Réserver un circuit pour être joignable derrière un NAT
+                        if circuit_listener.is_none() {
+                            if let Some(ref addr) = relay_multiaddr {
+                                match swarm.listen_on(addr.clone().with(Protocol::P2pCircuit)) {
+                                    Ok(id) => circuit_listener = Some(id),
+                                    Err(e) => warn!("⚠️  Réservation de circuit impossible: {}", e),
+                                }
+                            }
+                        }
                     }
                 }
                 SwarmEvent::ConnectionClosed { peer_id, num_established, cause, .. } => {
@@ -424,6 +1489,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         network_state.remove_peer(&peer_id.to_string()).await;
                         if Some(peer_id) == relay_peer_id {
                             connected_to_relay = false;
+                            if let Some(id) = circuit_listener.take() {
+                                swarm.remove_listener(id);
+                            }
                             info!("⚠️  Déconnecté du relay! Reconnexion dans 30s...");
                         }
                     }
@@ -443,14 +1511,99 @@ async fn main() -> Result<(), Box<dyn Error>> {
     }
 }
 
-/// Charge les bootstrap peers depuis le fichier bootstrap.txt
+/// Topic gossipsub d'un message : celui de son canal pour un post, le topic de base sinon
+fn gossip_topic(base: &str, msg: &NetworkMessage) -> IdentTopic {
+    match msg {
+        NetworkMessage::Post(post) => IdentTopic::new(channel::topic(base, post.channel())),
+        _ => IdentTopic::new(base),
+    }
+}
+
+/// Aligne les abonnements gossipsub sur les canaux suivis
+fn sync_channel_topics(
+    swarm: &mut libp2p::Swarm<ZetaBehaviour>,
+    base: &str,
+    wanted: &BTreeSet<String>,
+    subscribed: &mut BTreeSet<String>,
+    score_params: Option<&gossipsub::TopicScoreParams>,
+) {
+    for name in wanted.difference(subscribed) {
+        let topic = IdentTopic::new(channel::topic(base, name));
+        match swarm.behaviour_mut().gossipsub.subscribe(&topic) {
+            Ok(_) => info!("📡 Abonné au canal #{}", name),
+            Err(e) => warn!("⚠️  Abonnement au canal #{} impossible: {:?}", name, e),
+        }
+        if let Some(params) = score_params {
+            if let Err(e) = swarm.behaviour_mut().gossipsub.set_topic_params(topic, params.clone()) {
+                warn!("⚠️  Score du canal #{} non configuré: {}", name, e);
+            }
+        }
+    }
+    for name in subscribed.difference(wanted) {
+        match swarm.behaviour_mut().gossipsub.unsubscribe(&IdentTopic::new(channel::topic(base, name))) {
+            Ok(_) => info!("📴 Désabonné du canal #{}", name),
+            Err(e) => warn!("⚠️  Désabonnement du canal #{} impossible: {:?}", name, e),
+        }
+    }
+    *subscribed = wanted.clone();
+}
+
+/// Clé du débit gossip (`LimitScope::Peer`) d'un message reçu
+///
+/// Un message signé est compté pour son signataire, déjà vérifié : la source
+/// gossipsub d'un message relayé par un relay serait le relay lui-même. Les
+/// messages non signés et les types inconnus, relayés sans être contrôlés,
+/// sont comptés pour le pair qui les a transmis. Un message rejeté n'est pas
+/// compté : il pénalise déjà le score du pair.
+fn gossip_limit_key(verdict: &Result<Decoded, validation::Rejection>, propagation_source: &PeerId) -> Option<String> {
+    match verdict {
+        Ok(Decoded::Message(msg)) => {
+            Some(msg.signer().map(str::to_string).unwrap_or_else(|| propagation_source.to_string()))
+        }
+        Ok(Decoded::Unknown { .. }) => Some(propagation_source.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Verdict transmis à gossipsub pour un message reçu
+///
+/// Un rejet empêche le relais et pénalise le score du pair qui l'a transmis,
+/// un message ignoré (débit dépassé) n'est ni relayé ni pénalisé.
+fn gossip_acceptance(verdict: &Result<Decoded, validation::Rejection>, limited: bool) -> gossipsub::MessageAcceptance {
+    match verdict {
+        Err(_) => gossipsub::MessageAcceptance::Reject,
+        Ok(_) if limited => gossipsub::MessageAcceptance::Ignore,
+        Ok(_) => gossipsub::MessageAcceptance::Accept,
+    }
+}
+
+/// Charge la clé d'identité, ou en génère une nouvelle et la sauvegarde
+fn load_or_generate_identity(key_file: &Path) -> Result<Keypair, Box<dyn Error>> {
+    if key_file.exists() {
+        info!("🔐 Chargement des clés existantes...");
+        let key_bytes = fs::read(key_file)?;
+        let key = Keypair::from_protobuf_encoding(&key_bytes)?;
+        if signing::secret_key(&key).is_none() {
+            return Err(format!("{}: la clé d'identité doit être ed25519", key_file.display()).into());
+        }
+        Ok(key)
+    } else {
+        info!("🔑 Génération de nouvelles clés...");
+        let key = Keypair::generate_ed25519();
+        let key_bytes = key.to_protobuf_encoding()?;
+        fs::write(key_file, key_bytes)?;
+        info!("💾 Clés sauvegardées dans {}", key_file.display());
+        Ok(key)
+    }
+}
+
+/// Charge les bootstrap peers depuis le fichier de bootstrap (bootstrap.txt par défaut)
 /// Format: une ligne par peer avec l'adresse multiaddr complète
 /// Exemple: /ip4/65.75.201.11/tcp/4001/p2p/12D3KooWXYZ...
-fn load_bootstrap_peers() -> Vec<(PeerId, Multiaddr)> {
-    let bootstrap_file = "bootstrap.txt";
+fn load_bootstrap_peers(bootstrap_file: &Path) -> Vec<(PeerId, Multiaddr)> {
     let mut peers = Vec::new();
     
-    if !Path::new(bootstrap_file).exists() {
+    if !bootstrap_file.exists() {
         // Créer un fichier exemple
         let example = r#"# Bootstrap peers pour Zeta2
 # Une adresse multiaddr par ligne
@@ -468,7 +1621,7 @@ fn load_bootstrap_peers() -> Vec<(PeerId, Multiaddr)> {
     };
     
     let reader = BufReader::new(file);
-    for line in reader.lines().flatten() {
+    for line in reader.lines().map_while(Result::ok) {
         let line = line.trim();
         // Ignorer les commentaires et lignes vides
         if line.is_empty() || line.starts_with('#') {
@@ -479,7 +1632,7 @@ fn load_bootstrap_peers() -> Vec<(PeerId, Multiaddr)> {
         if let Ok(addr) = line.parse::<Multiaddr>() {
             // Extraire le PeerId de l'adresse
             let peer_id = addr.iter().find_map(|p| {
-                if let libp2p::multiaddr::Protocol::P2p(pid) = p {
+                if let Protocol::P2p(pid) = p {
                     Some(pid)
                 } else {
                     None
@@ -498,3 +1651,95 @@ fn load_bootstrap_peers() -> Vec<(PeerId, Multiaddr)> {
     
     peers
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gossipsub::MessageAcceptance;
+    use zeta_proto::MAX_POST_GRAPHEMES;
+
+    const ALICE: [u8; 32] = [1; 32];
+    const NOW: i64 = 1_700_000_000;
+
+    fn post(content: &str, timestamp: i64) -> NetworkMessage {
+        let mut post = Post {
+            id: "p1".to_string(),
+            author: signing::peer_id_from_secret_key(&ALICE),
+            author_name: "Alice".to_string(),
+            content: content.to_string(),
+            timestamp,
+            ..Default::default()
+        };
+        post.sign(&ALICE);
+        NetworkMessage::Post(post)
+    }
+
+    fn acceptance(bytes: &[u8], source: Option<&str>, limited: bool) -> MessageAcceptance {
+        gossip_acceptance(&validation::validate(bytes, source, NOW), limited)
+    }
+
+    #[test]
+    fn valid_messages_are_accepted_unless_limited() {
+        let bytes = envelope::encode(&post("Bonjour", NOW));
+        assert!(matches!(acceptance(&bytes, None, false), MessageAcceptance::Accept));
+        assert!(matches!(acceptance(&bytes, None, true), MessageAcceptance::Ignore));
+
+        // Un type inconnu circule sans être interprété
+        let unknown = br#"{"version":1,"kind":"Poll","payload":null}"#;
+        assert!(matches!(acceptance(unknown, None, false), MessageAcceptance::Accept));
+        assert!(matches!(acceptance(unknown, None, true), MessageAcceptance::Ignore));
+    }
+
+    #[test]
+    fn unknown_envelopes_are_limited_per_peer() {
+        let limits = RateLimiter::new([(LimitScope::Peer, 2)], Vec::new());
+        let relay = PeerId::random();
+        let mut verdicts = Vec::new();
+        for bytes in [
+            br#"{"version":1,"kind":"Poll","payload":null}"#.to_vec(),
+            br#"{"version":2,"kind":"Post","payload":{}}"#.to_vec(),
+            br#"{"version":9,"kind":"Autre","payload":[]}"#.to_vec(),
+        ] {
+            let verdict = validation::validate(&bytes, None, NOW);
+            let key = gossip_limit_key(&verdict, &relay);
+            assert_eq!(key.as_deref(), Some(relay.to_string().as_str()));
+            let limited = limits.check(&[(LimitScope::Peer, &key.unwrap())]).is_err();
+            verdicts.push(gossip_acceptance(&verdict, limited));
+        }
+        assert!(matches!(
+            verdicts.as_slice(),
+            [MessageAcceptance::Accept, MessageAcceptance::Accept, MessageAcceptance::Ignore]
+        ));
+
+        // Les messages signés sont comptés pour leur signataire, pas pour le relay
+        let signed = validation::validate(&envelope::encode(&post("Bonjour", NOW)), None, NOW);
+        assert_eq!(gossip_limit_key(&signed, &relay), Some(signing::peer_id_from_secret_key(&ALICE)));
+        assert_eq!(gossip_limit_key(&validation::validate(b"{", None, NOW), &relay), None);
+    }
+
+    #[test]
+    fn every_rejection_rule_rejects() {
+        let mut tampered = post("Bonjour", NOW);
+        if let NetworkMessage::Post(post) = &mut tampered {
+            post.content = "Au revoir".to_string();
+        }
+        let announce = NetworkMessage::RelayAnnounce {
+            peer_id: signing::peer_id_from_secret_key(&ALICE),
+            ws_url: "wss://relay".to_string(),
+        };
+        let cases = [
+            b"pas du json".to_vec(),
+            envelope::encode(&post(&"a".repeat(MAX_POST_GRAPHEMES + 1), NOW)),
+            envelope::encode(&post("Bonjour", NOW + validation::MAX_CLOCK_SKEW_SECS + 1)),
+            envelope::encode(&post("Bonjour", NOW - validation::MAX_MESSAGE_AGE_SECS - 1)),
+            envelope::encode(&tampered),
+            envelope::encode(&announce),
+            format!(r#"{{"version":2,"kind":"Post","payload":"{}"}}"#, "a".repeat(validation::MAX_UNKNOWN_ENVELOPE_BYTES)).into_bytes(),
+        ];
+        for bytes in &cases {
+            // Un message invalide est rejeté, même si son débit est aussi dépassé
+            assert!(matches!(acceptance(bytes, Some("12D3KooWautre"), false), MessageAcceptance::Reject));
+            assert!(matches!(acceptance(bytes, Some("12D3KooWautre"), true), MessageAcceptance::Reject));
+        }
+    }
+}
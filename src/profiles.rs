@@ -0,0 +1,52 @@
+//! Profils publics des identités
+//!
+//! Chaque nœud garde le dernier profil signé de chaque identité. Les noms
+//! affichés viennent d'ici : `Post::author_name` n'est qu'un repli pour les
+//! auteurs dont aucun profil n'a encore été reçu.
+
+use std::collections::HashMap;
+use zeta_proto::Profile;
+
+#[derive(Default)]
+pub struct ProfileCache {
+    /// identité -> dernier profil signé
+    by_identity: HashMap<String, Profile>,
+}
+
+impl ProfileCache {
+    /// Enregistre un profil, retourne `false` s'il n'est pas plus récent que
+    /// le dernier connu pour cette identité
+    pub fn apply(&mut self, profile: &Profile) -> bool {
+        if self.by_identity.get(&profile.identity).is_some_and(|known| known.timestamp >= profile.timestamp) {
+            return false;
+        }
+        self.by_identity.insert(profile.identity.clone(), profile.clone());
+        true
+    }
+
+    pub fn get(&self, identity: &str) -> Option<&Profile> {
+        self.by_identity.get(identity)
+    }
+
+    /// Nom affiché d'une identité, s'il vient d'un profil vérifié
+    pub fn display_name(&self, identity: &str) -> Option<String> {
+        self.get(identity).map(|profile| profile.name.clone())
+    }
+
+    /// Profils d'un ensemble d'identités, celles sans profil étant ignorées
+    pub fn for_identities<'a>(&self, identities: impl IntoIterator<Item = &'a str>) -> Vec<Profile> {
+        let mut profiles: Vec<Profile> = identities.into_iter().filter_map(|id| self.get(id)).cloned().collect();
+        profiles.sort_by(|a, b| a.identity.cmp(&b.identity));
+        profiles.dedup_by(|a, b| a.identity == b.identity);
+        profiles
+    }
+
+    /// Tous les profils connus, pour réécrire le journal ou initialiser un client
+    pub fn all(&self) -> Vec<Profile> {
+        self.by_identity.values().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_identity.len()
+    }
+}
@@ -0,0 +1,347 @@
+//! Limitation du débit de publication
+//!
+//! Un seau à jetons par connexion WebSocket, adresse IP, identité et pair
+//! libp2p. Chaque seau contient au plus `limite` jetons et se remplit de
+//! `limite` jetons par minute : une courte rafale passe, un flux continu non.
+//! Une publication doit trouver un jeton dans chacun de ses seaux, et n'en
+//! consomme que si tous en ont.
+//!
+//! Le nombre de seaux est borné : `prune`, appelé périodiquement, oublie les
+//! seaux de nouveau pleins puis les moins récemment utilisés au-delà de
+//! `MAX_BUCKETS`. Si des clés nouvelles affluent entre deux passages,
+//! `check` fait de même une fois `2 × MAX_BUCKETS` atteint, ce qui reste
+//! amorti.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zeta_proto::ws::LimitScope;
+
+/// Seaux conservés après un nettoyage
+const MAX_BUCKETS: usize = 4096;
+
+/// Intervalle entre deux nettoyages des seaux
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Nombre de seaux détaillés par portée dans l'état exposé
+const SNAPSHOT_BUCKETS: usize = 50;
+
+struct Bucket {
+    tokens: f64,
+    /// Dernier remplissage
+    updated: Instant,
+    /// Dernière publication comptée dans ce seau
+    used: Instant,
+    rejected: u64,
+}
+
+/// Publication refusée
+#[derive(Debug)]
+pub struct Limited {
+    pub scope: LimitScope,
+    /// Délai avant le prochain jeton
+    pub retry_after: Duration,
+}
+
+pub struct RateLimiter {
+    /// Publications par minute pour chaque portée, 0 pour ne pas limiter
+    limits: HashMap<LimitScope, u32>,
+    /// Proxys dont l'en-tête `X-Forwarded-For` est cru
+    trusted_proxies: Vec<IpAddr>,
+    buckets: Mutex<HashMap<(LimitScope, String), Bucket>>,
+    /// Refus cumulés par portée, seaux oubliés compris
+    rejected: HashMap<LimitScope, AtomicU64>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: impl IntoIterator<Item = (LimitScope, u32)>, trusted_proxies: Vec<IpAddr>) -> Self {
+        Self {
+            limits: limits.into_iter().collect(),
+            trusted_proxies,
+            buckets: Mutex::new(HashMap::new()),
+            rejected: LimitScope::ALL.into_iter().map(|scope| (scope, AtomicU64::new(0))).collect(),
+        }
+    }
+
+    fn limit(&self, scope: LimitScope) -> u32 {
+        self.limits.get(&scope).copied().unwrap_or(0)
+    }
+
+    /// Consomme un jeton dans chaque seau, ou aucun si l'un d'eux est vide
+    pub fn check(&self, keys: &[(LimitScope, &str)]) -> Result<(), Limited> {
+        self.check_at(keys, Instant::now())
+    }
+
+    fn check_at(&self, keys: &[(LimitScope, &str)], now: Instant) -> Result<(), Limited> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= 2 * MAX_BUCKETS {
+            self.prune_buckets(&mut buckets, now);
+        }
+
+        for (scope, key) in keys {
+            let limit = self.limit(*scope);
+            if limit == 0 {
+                continue;
+            }
+            let bucket = buckets.entry((*scope, key.to_string())).or_insert(Bucket {
+                tokens: limit as f64,
+                updated: now,
+                used: now,
+                rejected: 0,
+            });
+            refill(bucket, limit, now);
+            bucket.used = now;
+            if bucket.tokens < 1.0 {
+                bucket.rejected += 1;
+                self.rejected[scope].fetch_add(1, Ordering::Relaxed);
+                let retry_after = Duration::from_secs_f64((1.0 - bucket.tokens) * 60.0 / limit as f64);
+                return Err(Limited { scope: *scope, retry_after });
+            }
+        }
+        for (scope, key) in keys {
+            if let Some(bucket) = buckets.get_mut(&(*scope, key.to_string())) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Oublie le seau d'une clé, à la fermeture d'une connexion
+    pub fn forget(&self, scope: LimitScope, key: &str) {
+        self.buckets.lock().unwrap().remove(&(scope, key.to_string()));
+    }
+
+    /// Oublie les seaux pleins, puis les moins récemment utilisés au-delà de `MAX_BUCKETS`
+    pub fn prune(&self) {
+        let mut buckets = self.buckets.lock().unwrap();
+        self.prune_buckets(&mut buckets, Instant::now());
+    }
+
+    fn prune_buckets(&self, buckets: &mut HashMap<(LimitScope, String), Bucket>, now: Instant) {
+        buckets.retain(|(scope, _), bucket| {
+            let limit = self.limit(*scope);
+            refill(bucket, limit, now);
+            bucket.tokens < limit as f64
+        });
+        if buckets.len() <= MAX_BUCKETS {
+            return;
+        }
+        let mut used: Vec<Instant> = buckets.values().map(|bucket| bucket.used).collect();
+        let excess = buckets.len() - MAX_BUCKETS;
+        let (_, cutoff, _) = used.select_nth_unstable(excess - 1);
+        let cutoff = *cutoff;
+        let mut evicted = 0;
+        buckets.retain(|_, bucket| {
+            if evicted < excess && bucket.used <= cutoff {
+                evicted += 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    /// Adresse du client : celle de la connexion, ou celle annoncée par un
+    /// proxy de confiance dans `X-Forwarded-For`
+    ///
+    /// L'en-tête est lu de droite à gauche en sautant les proxys de confiance :
+    /// les adresses plus à gauche ont pu être écrites par le client lui-même.
+    pub fn client_ip(&self, remote: Option<IpAddr>, forwarded_for: Option<&str>) -> Option<IpAddr> {
+        let remote = remote?;
+        if !self.trusted_proxies.contains(&remote) {
+            return Some(remote);
+        }
+        let Some(forwarded_for) = forwarded_for else {
+            return Some(remote);
+        };
+        let mut client = remote;
+        for hop in forwarded_for.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) => {
+                    client = ip;
+                    if !self.trusted_proxies.contains(&ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        Some(client)
+    }
+
+    /// Limites et seaux entamés, les plus vides d'abord
+    pub fn snapshot(&self) -> serde_json::Value {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut scopes = serde_json::Map::new();
+        for scope in LimitScope::ALL {
+            let limit = self.limit(scope);
+            let mut active: Vec<(&String, &Bucket)> = buckets
+                .iter_mut()
+                .filter(|((s, _), _)| *s == scope)
+                .map(|((_, key), bucket)| {
+                    refill(bucket, limit, now);
+                    (key, &*bucket)
+                })
+                .filter(|(_, bucket)| bucket.tokens < limit as f64)
+                .collect();
+            active.sort_by(|a, b| a.1.tokens.total_cmp(&b.1.tokens));
+            let tracked = active.len();
+            let details: Vec<serde_json::Value> = active
+                .into_iter()
+                .take(SNAPSHOT_BUCKETS)
+                .map(|(key, bucket)| serde_json::json!({
+                    "key": key,
+                    "tokens": (bucket.tokens * 100.0).floor() / 100.0,
+                    "rejected": bucket.rejected,
+                }))
+                .collect();
+            scopes.insert(scope.as_str().to_string(), serde_json::json!({
+                "per_minute": limit,
+                "rejected": self.rejected[&scope].load(Ordering::Relaxed),
+                "active": tracked,
+                "buckets": details,
+            }));
+        }
+        serde_json::json!({
+            "trusted_proxies": self.trusted_proxies,
+            "scopes": scopes,
+        })
+    }
+}
+
+/// Ajoute les jetons gagnés depuis la dernière mise à jour
+fn refill(bucket: &mut Bucket, limit: u32, now: Instant) {
+    let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * limit as f64 / 60.0).min(limit as f64);
+    bucket.updated = now;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(limit: u32) -> RateLimiter {
+        RateLimiter::new([(LimitScope::Ip, limit)], Vec::new())
+    }
+
+    #[test]
+    fn burst_then_refill() {
+        let limiter = limiter(6);
+        let start = Instant::now();
+        let ip = [(LimitScope::Ip, "10.0.0.1")];
+        for _ in 0..6 {
+            limiter.check_at(&ip, start).unwrap();
+        }
+        // 6 jetons par minute : un nouveau jeton toutes les 10 s
+        let limited = limiter.check_at(&ip, start).unwrap_err();
+        assert_eq!(limited.scope, LimitScope::Ip);
+        assert_eq!(limited.retry_after, Duration::from_secs(10));
+
+        let limited = limiter.check_at(&ip, start + Duration::from_secs(4)).unwrap_err();
+        assert!((limited.retry_after.as_secs_f64() - 6.0).abs() < 1e-6);
+
+        limiter.check_at(&ip, start + Duration::from_secs(10)).unwrap();
+        assert!(limiter.check_at(&ip, start + Duration::from_secs(10)).is_err());
+
+        // Le seau ne dépasse jamais sa limite
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..6 {
+            limiter.check_at(&ip, later).unwrap();
+        }
+        assert!(limiter.check_at(&ip, later).is_err());
+    }
+
+    #[test]
+    fn refused_publication_consumes_no_token() {
+        let limiter = RateLimiter::new([(LimitScope::Ip, 10), (LimitScope::Identity, 1)], Vec::new());
+        let now = Instant::now();
+        let keys = [(LimitScope::Ip, "10.0.0.1"), (LimitScope::Identity, "alice")];
+        limiter.check_at(&keys, now).unwrap();
+        for _ in 0..5 {
+            assert_eq!(limiter.check_at(&keys, now).unwrap_err().scope, LimitScope::Identity);
+        }
+        // L'IP n'a payé que la publication acceptée
+        for _ in 0..9 {
+            limiter.check_at(&[(LimitScope::Ip, "10.0.0.1")], now).unwrap();
+        }
+        assert!(limiter.check_at(&[(LimitScope::Ip, "10.0.0.1")], now).is_err());
+    }
+
+    #[test]
+    fn unlimited_scope_is_not_tracked() {
+        let limiter = limiter(0);
+        for _ in 0..100 {
+            limiter.check(&[(LimitScope::Ip, "10.0.0.1")]).unwrap();
+        }
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn forwarded_for_is_read_only_from_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let limiter = RateLimiter::new([], vec![proxy]);
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // Un client direct ne peut pas choisir son adresse
+        assert_eq!(limiter.client_ip(Some(client), Some("198.51.100.1")), Some(client));
+        assert_eq!(limiter.client_ip(Some(proxy), Some("203.0.113.7")), Some(client));
+        assert_eq!(limiter.client_ip(Some(proxy), None), Some(proxy));
+        assert_eq!(limiter.client_ip(None, Some("203.0.113.7")), None);
+    }
+
+    #[test]
+    fn spoofed_left_hops_are_ignored() {
+        let proxies: Vec<IpAddr> = vec!["10.0.0.2".parse().unwrap(), "10.0.0.3".parse().unwrap()];
+        let limiter = RateLimiter::new([], proxies.clone());
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // Le client a écrit les deux premières adresses lui-même
+        let header = "1.2.3.4, 198.51.100.1, 203.0.113.7, 10.0.0.3";
+        assert_eq!(limiter.client_ip(Some(proxies[0]), Some(header)), Some(client));
+        // Une entrée illisible arrête la lecture sur le dernier saut sûr
+        assert_eq!(limiter.client_ip(Some(proxies[0]), Some("203.0.113.7, garbage, 10.0.0.3")), Some(proxies[1]));
+    }
+
+    #[test]
+    fn prune_evicts_least_recently_used_buckets() {
+        let limiter = limiter(10);
+        let start = Instant::now();
+        for i in 0..MAX_BUCKETS + 10 {
+            let now = start + Duration::from_millis(i as u64);
+            limiter.check_at(&[(LimitScope::Ip, &format!("10.0.{}.{}", i / 256, i % 256))], now).unwrap();
+        }
+        // Le premier client publie de nouveau : il devient le plus récent
+        limiter.check_at(&[(LimitScope::Ip, "10.0.0.0")], start + Duration::from_secs(5)).unwrap();
+
+        let mut buckets = limiter.buckets.lock().unwrap();
+        limiter.prune_buckets(&mut buckets, start + Duration::from_secs(5));
+        assert_eq!(buckets.len(), MAX_BUCKETS);
+        assert!(buckets.contains_key(&(LimitScope::Ip, "10.0.0.0".to_string())));
+        assert!(!buckets.contains_key(&(LimitScope::Ip, "10.0.0.1".to_string())));
+        assert!(buckets.contains_key(&(LimitScope::Ip, "10.0.16.9".to_string())));
+    }
+
+    #[test]
+    fn prune_forgets_full_buckets() {
+        let limiter = limiter(60);
+        let start = Instant::now();
+        limiter.check_at(&[(LimitScope::Ip, "10.0.0.1")], start).unwrap();
+        let mut buckets = limiter.buckets.lock().unwrap();
+        limiter.prune_buckets(&mut buckets, start + Duration::from_millis(500));
+        assert_eq!(buckets.len(), 1);
+        limiter.prune_buckets(&mut buckets, start + Duration::from_secs(1));
+        assert!(buckets.is_empty());
+    }
+
+    #[test]
+    fn check_bounds_buckets_between_prunes() {
+        let limiter = limiter(10);
+        let now = Instant::now();
+        for i in 0..2 * MAX_BUCKETS + 1 {
+            limiter.check_at(&[(LimitScope::Ip, &i.to_string())], now).unwrap();
+        }
+        assert!(limiter.buckets.lock().unwrap().len() <= MAX_BUCKETS + 1);
+    }
+}
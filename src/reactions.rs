@@ -0,0 +1,72 @@
+//! Réactions agrégées par post
+//!
+//! Chaque relay compte les réactions qu'il reçoit (gossipsub, WebSocket ou
+//! synchronisation). Une identité ne compte qu'une fois par emoji sur un même
+//! post, même si sa réaction est rediffusée ou renvoyée. Les réactions
+//! signées sont gardées pour être journalisées et transmises aux pairs qui
+//! se synchronisent.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use zeta_proto::{Reaction, ReactionCounts};
+
+#[derive(Default)]
+pub struct Reactions {
+    /// post -> emoji -> auteur -> réaction signée
+    by_post: HashMap<String, HashMap<String, HashMap<String, Reaction>>>,
+}
+
+impl Reactions {
+    /// Enregistre une réaction, retourne `false` si l'auteur avait déjà réagi ainsi
+    pub fn add(&mut self, reaction: &Reaction) -> bool {
+        let authors = self
+            .by_post
+            .entry(reaction.post_id.clone())
+            .or_default()
+            .entry(reaction.emoji.clone())
+            .or_default();
+        match authors.entry(reaction.author.clone()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(reaction.clone());
+                true
+            }
+        }
+    }
+
+    pub fn counts(&self, post_id: &str) -> ReactionCounts {
+        self.by_post
+            .get(post_id)
+            .map(|emojis| emojis.iter().map(|(emoji, authors)| (emoji.clone(), authors.len())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Compteurs de tous les posts ayant reçu au moins une réaction
+    pub fn all_counts(&self) -> HashMap<String, ReactionCounts> {
+        self.by_post.keys().map(|post_id| (post_id.clone(), self.counts(post_id))).collect()
+    }
+
+    /// Réactions signées d'un post
+    pub fn for_post(&self, post_id: &str) -> impl Iterator<Item = &Reaction> {
+        self.by_post.get(post_id).into_iter().flat_map(|emojis| emojis.values().flat_map(HashMap::values))
+    }
+
+    /// Réactions signées d'une liste de posts, pour une réponse de synchronisation
+    pub fn for_posts<'a>(&'a self, post_ids: impl IntoIterator<Item = &'a str> + 'a) -> impl Iterator<Item = Reaction> + 'a {
+        post_ids.into_iter().flat_map(|post_id| self.for_post(post_id)).cloned()
+    }
+
+    /// Toutes les réactions signées, pour réécrire le journal
+    pub fn all(&self) -> Vec<Reaction> {
+        self.by_post.keys().flat_map(|post_id| self.for_post(post_id)).cloned().collect()
+    }
+
+    /// Oublie les réactions des posts qui ne sont plus conservés
+    pub fn retain_posts(&mut self, keep: impl Fn(&str) -> bool) {
+        self.by_post.retain(|post_id, _| keep(post_id));
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_post.len()
+    }
+}
@@ -0,0 +1,173 @@
+//! Score des pairs gossipsub
+//!
+//! Paramètres dérivés de la section `[peer_scoring]` de la configuration. Le
+//! trafic d'un réseau social est faible et irrégulier : la pénalité pour trop
+//! peu de messages livrés dans le mesh (P3) est désactivée, elle exclurait
+//! des pairs simplement silencieux. Le score repose sur le temps passé dans
+//! le mesh, les premières livraisons et surtout les messages rejetés par la
+//! validation.
+
+use crate::config::PeerScoring;
+use libp2p::gossipsub::{PeerScoreParams, PeerScoreThresholds, TopicScoreParams};
+use std::time::Duration;
+
+/// Paramètres globaux et seuils
+pub fn params(config: &PeerScoring) -> (PeerScoreParams, PeerScoreThresholds) {
+    let params = PeerScoreParams {
+        ip_colocation_factor_threshold: config.ip_colocation_threshold,
+        ..PeerScoreParams::default()
+    };
+    let thresholds = PeerScoreThresholds {
+        gossip_threshold: config.gossip_threshold,
+        publish_threshold: config.publish_threshold,
+        graylist_threshold: config.graylist_threshold,
+        accept_px_threshold: config.accept_px_threshold,
+        opportunistic_graft_threshold: config.opportunistic_graft_threshold,
+    };
+    (params, thresholds)
+}
+
+/// Paramètres appliqués à chaque topic suivi (topic principal et canaux)
+pub fn topic_params(config: &PeerScoring) -> TopicScoreParams {
+    TopicScoreParams {
+        topic_weight: config.topic_weight,
+        // P1 : une heure dans le mesh rapporte au plus 36 points
+        time_in_mesh_weight: 0.01,
+        time_in_mesh_quantum: Duration::from_secs(1),
+        time_in_mesh_cap: 3600.0,
+        // P2
+        first_message_deliveries_weight: config.first_delivery_weight,
+        first_message_deliveries_decay: 0.99,
+        first_message_deliveries_cap: config.first_delivery_cap,
+        // P3 et P3b désactivés
+        mesh_message_deliveries_weight: 0.0,
+        mesh_failure_penalty_weight: 0.0,
+        // P4
+        invalid_message_deliveries_weight: config.invalid_message_weight,
+        invalid_message_deliveries_decay: config.invalid_message_decay,
+        ..TopicScoreParams::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use libp2p::gossipsub::{self, IdentTopic, MessageAcceptance, MessageAuthenticity};
+    use libp2p::swarm::SwarmEvent;
+    use libp2p::{noise, tcp, yamux, Multiaddr, Swarm, SwarmBuilder};
+    use tokio::time::{timeout, Instant};
+
+    fn swarm(scoring: Option<&PeerScoring>, topic: &IdentTopic) -> Swarm<gossipsub::Behaviour> {
+        let mut swarm = SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(tcp::Config::default().nodelay(true), noise::Config::new, yamux::Config::default)
+            .unwrap()
+            .with_behaviour(|key| {
+                let config = gossipsub::ConfigBuilder::default()
+                    .heartbeat_interval(Duration::from_millis(100))
+                    .validation_mode(gossipsub::ValidationMode::Strict)
+                    .validate_messages()
+                    .build()
+                    .unwrap();
+                let mut gossipsub = gossipsub::Behaviour::new(MessageAuthenticity::Signed(key.clone()), config).unwrap();
+                if let Some(scoring) = scoring {
+                    let (mut params, thresholds) = params(scoring);
+                    params.topics.insert(topic.hash(), topic_params(scoring));
+                    gossipsub.with_peer_score(params, thresholds).unwrap();
+                }
+                gossipsub
+            })
+            .unwrap()
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(30)))
+            .build();
+        swarm.behaviour_mut().subscribe(topic).unwrap();
+        swarm
+    }
+
+    /// Fait tourner les deux swarms pendant `duration`, en rejetant tout
+    /// message reçu par le nœud noté ; retourne le nombre de messages reçus
+    async fn run(
+        node: &mut Swarm<gossipsub::Behaviour>,
+        attacker: &mut Swarm<gossipsub::Behaviour>,
+        duration: Duration,
+    ) -> usize {
+        let deadline = Instant::now() + duration;
+        let mut received = 0;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return received;
+            }
+            tokio::select! {
+                event = node.select_next_some() => {
+                    if let SwarmEvent::Behaviour(gossipsub::Event::Message { propagation_source, message_id, .. }) = event {
+                        received += 1;
+                        node.behaviour_mut()
+                            .report_message_validation_result(&message_id, &propagation_source, MessageAcceptance::Reject)
+                            .unwrap();
+                    }
+                }
+                _ = attacker.select_next_some() => {}
+                _ = tokio::time::sleep(remaining) => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn peer_sending_rejected_messages_is_graylisted() {
+        let config = PeerScoring::default();
+        let topic = IdentTopic::new("zeta2-test-scoring");
+        let mut node = swarm(Some(&config), &topic);
+        let mut attacker = swarm(None, &topic);
+
+        node.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+        let address: Multiaddr = timeout(Duration::from_secs(5), async {
+            loop {
+                if let SwarmEvent::NewListenAddr { address, .. } = node.select_next_some().await {
+                    return address;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        attacker.dial(address).unwrap();
+
+        // Attendre que l'attaquant sache le nœud abonné au topic
+        let node_id = *node.local_peer_id();
+        timeout(Duration::from_secs(5), async {
+            loop {
+                tokio::select! {
+                    _ = node.select_next_some() => {}
+                    event = attacker.select_next_some() => {
+                        if let SwarmEvent::Behaviour(gossipsub::Event::Subscribed { peer_id, .. }) = event {
+                            if peer_id == node_id {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        let attacker_id = *attacker.local_peer_id();
+        let mut sent = 0;
+        let mut score = 0.0;
+        while score >= config.graylist_threshold {
+            assert!(sent < 10, "score {} après {} rejets", score, sent);
+            attacker.behaviour_mut().publish(topic.clone(), format!("invalide {}", sent)).unwrap();
+            sent += 1;
+            assert_eq!(run(&mut node, &mut attacker, Duration::from_millis(300)).await, 1);
+            score = node.behaviour().peer_score(&attacker_id).unwrap();
+        }
+
+        // Sous le seuil de graylist, ses messages suivants sont ignorés
+        for _ in 0..5 {
+            attacker.behaviour_mut().publish(topic.clone(), format!("invalide {}", sent)).unwrap();
+            sent += 1;
+        }
+        assert_eq!(run(&mut node, &mut attacker, Duration::from_secs(1)).await, 0);
+    }
+}
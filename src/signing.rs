@@ -0,0 +1,14 @@
+//! Signatures ed25519 avec l'identité libp2p du nœud
+//!
+//! L'encodage canonique et la vérification sont fournis par `zeta_proto` ;
+//! ce module ne fait qu'extraire la clé privée de la paire de clés libp2p.
+
+use libp2p::identity::Keypair;
+
+pub use zeta_proto::signing::*;
+
+/// Clé privée ed25519 brute de l'identité libp2p
+pub fn secret_key(key: &Keypair) -> Option<[u8; 32]> {
+    let keypair = key.clone().try_into_ed25519().ok()?;
+    keypair.secret().as_ref().try_into().ok()
+}
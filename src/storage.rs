@@ -0,0 +1,364 @@
+//! Stockage persistant des posts
+//!
+//! `NetworkState` garde les posts récents en mémoire et délègue leur
+//! persistance à un `PostStore`. Le backend par défaut est un journal
+//! JSON-lines : chaque post accepté est ajouté en fin de fichier, et le
+//! journal est réécrit quand il dépasse largement la limite de rétention.
+//! Une modification est ajoutée comme une nouvelle version du post : au
+//! rechargement, la dernière ligne d'un identifiant l'emporte. Les
+//! suppressions, les réactions, les abonnements et les profils sont
+//! conservés dans des journaux séparés.
+
+use crate::{NetworkMessage, Post};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zeta_proto::{PostDelete, Profile, Reaction};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Backend de stockage des posts
+pub trait PostStore: Send + Sync {
+    /// Recharge les posts sauvegardés, du plus récent au plus ancien
+    fn load(&self) -> io::Result<Vec<Post>>;
+
+    /// Ajoute un post au stockage
+    fn append(&self, post: &Post) -> io::Result<()>;
+
+    /// Remplace le contenu du stockage par les posts retenus (plus récent en premier)
+    fn compact(&self, posts: &[Post]) -> io::Result<()>;
+
+    /// Indique si le stockage mérite d'être compacté
+    fn should_compact(&self, _retained: usize) -> bool {
+        false
+    }
+
+    /// Recharge les suppressions enregistrées
+    fn load_tombstones(&self) -> io::Result<Vec<PostDelete>> {
+        Ok(Vec::new())
+    }
+
+    /// Enregistre une suppression
+    fn append_tombstone(&self, _delete: &PostDelete) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Indique si le journal des suppressions mérite d'être réécrit
+    fn should_compact_tombstones(&self, _retained: usize) -> bool {
+        false
+    }
+
+    /// Réécrit le journal des suppressions avec les pierres tombales conservées
+    fn compact_tombstones(&self, _tombstones: &[PostDelete]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Recharge les réactions enregistrées
+    fn load_reactions(&self) -> io::Result<Vec<Reaction>> {
+        Ok(Vec::new())
+    }
+
+    /// Enregistre une réaction
+    fn append_reaction(&self, _reaction: &Reaction) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Réécrit le journal des réactions avec celles des posts conservés
+    fn compact_reactions(&self, _reactions: &[Reaction]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Recharge les abonnements et désabonnements (`NetworkMessage::Follow` / `Unfollow`)
+    fn load_follows(&self) -> io::Result<Vec<NetworkMessage>> {
+        Ok(Vec::new())
+    }
+
+    /// Enregistre un abonnement ou un désabonnement
+    fn append_follow(&self, _follow: &NetworkMessage) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Réécrit le journal des abonnements avec les derniers messages connus
+    fn compact_follows(&self, _follows: &[NetworkMessage]) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Recharge les profils enregistrés
+    fn load_profiles(&self) -> io::Result<Vec<Profile>> {
+        Ok(Vec::new())
+    }
+
+    /// Enregistre un profil
+    fn append_profile(&self, _profile: &Profile) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Réécrit le journal des profils avec le dernier profil de chaque identité
+    fn compact_profiles(&self, _profiles: &[Profile]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Aucun stockage : les posts sont perdus au redémarrage
+pub struct MemoryPostStore;
+
+impl PostStore for MemoryPostStore {
+    fn load(&self) -> io::Result<Vec<Post>> {
+        Ok(Vec::new())
+    }
+
+    fn append(&self, _post: &Post) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn compact(&self, _posts: &[Post]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Journal JSON-lines, un post par ligne dans l'ordre d'arrivée
+pub struct JsonlPostStore {
+    path: PathBuf,
+    /// Journal des suppressions, à côté du journal des posts
+    tombstones_path: PathBuf,
+    tombstone_entries: AtomicUsize,
+    /// Journal des réactions
+    reactions_path: PathBuf,
+    reaction_entries: AtomicUsize,
+    /// Journal des abonnements
+    follows_path: PathBuf,
+    follow_entries: AtomicUsize,
+    /// Journal des profils
+    profiles_path: PathBuf,
+    profile_entries: AtomicUsize,
+    file: Mutex<()>,
+    entries: AtomicUsize,
+}
+
+impl JsonlPostStore {
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            tombstones_path: path.with_extension("tombstones.jsonl"),
+            tombstone_entries: AtomicUsize::new(0),
+            reactions_path: path.with_extension("reactions.jsonl"),
+            reaction_entries: AtomicUsize::new(0),
+            follows_path: path.with_extension("follows.jsonl"),
+            follow_entries: AtomicUsize::new(0),
+            profiles_path: path.with_extension("profiles.jsonl"),
+            profile_entries: AtomicUsize::new(0),
+            path,
+            file: Mutex::new(()),
+            entries: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl PostStore for JsonlPostStore {
+    fn load(&self) -> io::Result<Vec<Post>> {
+        let _guard = self.file.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut posts = Vec::new();
+        let mut entries = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries += 1;
+            match serde_json::from_str::<Post>(&line) {
+                Ok(post) => posts.push(post),
+                Err(e) => warn!("⚠️  Ligne invalide dans {}: {}", self.path.display(), e),
+            }
+        }
+        self.entries.store(entries, Ordering::Relaxed);
+
+        posts.reverse();
+        Ok(posts)
+    }
+
+    fn append(&self, post: &Post) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let mut line = serde_json::to_vec(post)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        self.entries.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn compact(&self, posts: &[Post]) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        // Écrire dans un fichier temporaire puis renommer pour ne jamais tronquer le journal
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        {
+            let mut file = File::create(&tmp_path)?;
+            for post in posts.iter().rev() {
+                let mut line = serde_json::to_vec(post)?;
+                line.push(b'\n');
+                file.write_all(&line)?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &self.path)?;
+        self.entries.store(posts.len(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn should_compact(&self, retained: usize) -> bool {
+        self.entries.load(Ordering::Relaxed) > retained.saturating_mul(2)
+    }
+
+    fn load_tombstones(&self) -> io::Result<Vec<PostDelete>> {
+        let _guard = self.file.lock().unwrap();
+        let tombstones: Vec<PostDelete> = read_lines(&self.tombstones_path)?;
+        self.tombstone_entries.store(tombstones.len(), Ordering::Relaxed);
+        Ok(tombstones)
+    }
+
+    fn append_tombstone(&self, delete: &PostDelete) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        append_line(&self.tombstones_path, delete)?;
+        self.tombstone_entries.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn should_compact_tombstones(&self, retained: usize) -> bool {
+        self.tombstone_entries.load(Ordering::Relaxed) > retained.saturating_mul(2).max(64)
+    }
+
+    fn compact_tombstones(&self, tombstones: &[PostDelete]) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        rewrite_lines(&self.tombstones_path, tombstones)?;
+        self.tombstone_entries.store(tombstones.len(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn load_reactions(&self) -> io::Result<Vec<Reaction>> {
+        let _guard = self.file.lock().unwrap();
+        let reactions: Vec<Reaction> = read_lines(&self.reactions_path)?;
+        self.reaction_entries.store(reactions.len(), Ordering::Relaxed);
+        Ok(reactions)
+    }
+
+    fn append_reaction(&self, reaction: &Reaction) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        append_line(&self.reactions_path, reaction)?;
+        self.reaction_entries.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn compact_reactions(&self, reactions: &[Reaction]) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        // Les réactions des posts oubliés ou supprimés restent jusqu'à la réécriture
+        if self.reaction_entries.load(Ordering::Relaxed) <= reactions.len().saturating_mul(2).max(64) {
+            return Ok(());
+        }
+        rewrite_lines(&self.reactions_path, reactions)?;
+        self.reaction_entries.store(reactions.len(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn load_follows(&self) -> io::Result<Vec<NetworkMessage>> {
+        let _guard = self.file.lock().unwrap();
+        let follows: Vec<NetworkMessage> = read_lines(&self.follows_path)?;
+        self.follow_entries.store(follows.len(), Ordering::Relaxed);
+        Ok(follows)
+    }
+
+    fn append_follow(&self, follow: &NetworkMessage) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        append_line(&self.follows_path, follow)?;
+        self.follow_entries.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn compact_follows(&self, follows: &[NetworkMessage]) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        // Peu d'abonnements changent souvent : réécrire seulement si le journal a doublé
+        if self.follow_entries.load(Ordering::Relaxed) <= follows.len().saturating_mul(2).max(64) {
+            return Ok(());
+        }
+        rewrite_lines(&self.follows_path, follows)?;
+        self.follow_entries.store(follows.len(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn load_profiles(&self) -> io::Result<Vec<Profile>> {
+        let _guard = self.file.lock().unwrap();
+        let profiles: Vec<Profile> = read_lines(&self.profiles_path)?;
+        self.profile_entries.store(profiles.len(), Ordering::Relaxed);
+        Ok(profiles)
+    }
+
+    fn append_profile(&self, profile: &Profile) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        append_line(&self.profiles_path, profile)?;
+        self.profile_entries.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn compact_profiles(&self, profiles: &[Profile]) -> io::Result<()> {
+        let _guard = self.file.lock().unwrap();
+        // Les anciennes versions d'un profil ne servent plus : réécrire quand elles dominent
+        if self.profile_entries.load(Ordering::Relaxed) <= profiles.len().saturating_mul(2).max(64) {
+            return Ok(());
+        }
+        rewrite_lines(&self.profiles_path, profiles)?;
+        self.profile_entries.store(profiles.len(), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Relit un journal JSON-lines en ignorant les lignes illisibles
+fn read_lines<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut values = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<T>(&line) {
+            Ok(value) => values.push(value),
+            Err(e) => warn!("⚠️  Ligne invalide dans {}: {}", path.display(), e),
+        }
+    }
+    Ok(values)
+}
+
+/// Ajoute une valeur en fin de journal JSON-lines
+fn append_line<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    file.write_all(&line)
+}
+
+/// Réécrit un journal JSON-lines via un fichier temporaire, sans jamais le tronquer
+fn rewrite_lines<T: Serialize>(path: &Path, values: &[T]) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = File::create(&tmp_path)?;
+        for value in values {
+            let mut line = serde_json::to_vec(value)?;
+            line.push(b'\n');
+            file.write_all(&line)?;
+        }
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
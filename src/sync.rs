@@ -0,0 +1,32 @@
+//! Protocole de synchronisation de l'historique (`/zeta2/sync/1.0.0`)
+//!
+//! Gossipsub ne rejoue pas les messages passés : un nœud qui arrive tard
+//! demande donc à chaque nouveau pair les posts qu'il a manqués, avec leurs
+//! réactions, puis les fusionne dans `NetworkState` en ignorant ceux qu'il
+//! connaît déjà.
+
+use libp2p::request_response::{self, ProtocolSupport};
+use libp2p::StreamProtocol;
+use std::time::Duration;
+use zeta_proto::{SyncRequest, SyncResponse};
+
+pub const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/zeta2/sync/1.0.0");
+
+/// Nombre maximum de posts renvoyés par réponse
+pub const SYNC_MAX_POSTS: usize = 500;
+
+/// Nombre maximum de réactions renvoyées par réponse
+pub const SYNC_MAX_REACTIONS: usize = 2000;
+
+/// Recouvrement (secondes) appliqué au dernier timestamp connu pour combler
+/// les posts arrivés dans le désordre
+pub const SYNC_OVERLAP_SECS: i64 = 600;
+
+pub type Behaviour = request_response::json::Behaviour<SyncRequest, SyncResponse>;
+
+pub fn new_behaviour() -> Behaviour {
+    request_response::json::Behaviour::new(
+        [(SYNC_PROTOCOL, ProtocolSupport::Full)],
+        request_response::Config::default().with_request_timeout(Duration::from_secs(30)),
+    )
+}
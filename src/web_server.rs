@@ -1,13 +1,23 @@
 //! Serveur web avec WebSocket pour clients navigateur
 
-use crate::{NetworkMessage, NetworkState, PeerInfo, Post};
+use crate::blobs::BlobError;
+use crate::channels::MAX_CHANNELS_PER_CONNECTION;
+use crate::ratelimit::Limited;
+use crate::{signing, Attachment, Ingest, NetworkMessage, NetworkState, PeerInfo, Post};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use warp::ws::{Message, WebSocket};
+use warp::http::StatusCode;
 use warp::{Filter, Rejection, Reply};
+use std::collections::BTreeSet;
+use zeta_proto::{blob, channel, validation, Profile, ReactionCounts, MAX_POST_GRAPHEMES};
+use zeta_proto::ws::{self as proto_ws, ClientFrame, ErrorCode, FeedMode, LimitScope, PostPayload, ServerFrame};
 
 #[derive(Serialize)]
 struct NetworkInfo {
@@ -16,20 +26,90 @@ struct NetworkInfo {
     peers: Vec<PeerInfo>,
     posts: Vec<Post>,
     is_relay: bool,
+    /// Messages gossip illisibles ou de type inconnu
+    messages: serde_json::Value,
+    /// Réactions par post
+    reactions: HashMap<String, ReactionCounts>,
+    /// Messages directs chiffrés en attente sur ce relay
+    direct_messages: usize,
+    /// Profils vérifiés des auteurs, pour afficher leur nom
+    profiles: Vec<Profile>,
+    /// Occupation du stockage des fichiers joints
+    blobs: serde_json::Value,
+    /// Score gossipsub de chaque pair, vide si le score est désactivé
+    peer_scores: HashMap<String, f64>,
+}
+
+#[derive(Deserialize)]
+struct EditRequest {
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct FeedQuery {
+    /// Identité dont on veut le fil personnalisé, fil global sinon
+    identity: Option<String>,
+    /// Limite le fil à un canal
+    channel: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DirectRequest {
+    recipient: String,
+    content: String,
+}
+
+/// Message direct du nœud local, déchiffré
+#[derive(Serialize)]
+struct DirectView {
+    sender: String,
+    recipient: String,
+    timestamp: i64,
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ProfileRequest {
+    name: String,
+    #[serde(default)]
+    bio: String,
+    #[serde(default)]
+    avatar: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ReactRequest {
+    post_id: String,
+    emoji: String,
 }
 
 #[derive(Deserialize)]
 struct PostRequest {
     content: String,
     author_name: String,
+    /// Post auquel on répond
+    #[serde(default)]
+    reply_to: Option<String>,
+    /// Canal du post (`general` par défaut, celui du parent pour une réponse)
+    #[serde(default)]
+    channel: Option<String>,
+    /// Fichiers déjà envoyés avec `POST /api/blobs`
+    #[serde(default)]
+    attachments: Vec<Attachment>,
 }
 
 #[derive(Deserialize)]
-struct WsMessage {
-    #[serde(rename = "type")]
-    msg_type: String,
-    content: Option<String>,
-    author_name: Option<String>,
+struct BlobQuery {
+    /// Nom d'origine du fichier
+    name: Option<String>,
+}
+
+/// Vérifie qu'un client a signé le nonce avec la clé correspondant au PeerId annoncé
+fn verify_identity(peer_id: &str, public_key: &str, signature: &str, nonce: &str) -> bool {
+    if signing::peer_id_from_public_key_hex(public_key).as_deref() != Some(peer_id) {
+        return false;
+    }
+    signing::verify(public_key, signature, &proto_ws::identify_bytes(nonce, peer_id))
 }
 
 type SharedState = Arc<RwLock<(NetworkState, mpsc::UnboundedSender<Post>, mpsc::UnboundedSender<NetworkMessage>, String, bool)>>;
@@ -41,6 +121,7 @@ pub async fn start_server(
     local_name: String,
     is_relay: bool,
     port: u16,
+    static_dir: PathBuf,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let shared_state = Arc::new(RwLock::new((
         network_state.clone(),
@@ -51,7 +132,7 @@ pub async fn start_server(
     )));
 
     // Route pour servir les fichiers statiques
-    let static_files = warp::fs::dir("./static");
+    let static_files = warp::fs::dir(static_dir.clone());
 
     // Route pour obtenir l'état du réseau (REST API)
     let state = shared_state.clone();
@@ -61,40 +142,255 @@ pub async fn start_server(
         .and(with_state(state))
         .and_then(get_network_info);
 
+    // Les routes qui signent avec l'identité du nœud ou changent ce qu'il suit
+    // sont réservées à son opérateur (`operator_only`)
+
     // Route pour poster un message (REST API)
     let state = shared_state.clone();
     let post_message = warp::path("api")
         .and(warp::path("post"))
         .and(warp::post())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
         .and(warp::body::json())
         .and(with_state(state))
         .and_then(create_post);
 
+    // Route pour réagir à un post (REST API)
+    let state = shared_state.clone();
+    let react = warp::path("api")
+        .and(warp::path("react"))
+        .and(warp::post())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(state))
+        .and_then(create_reaction);
+
+    // Route pour un post et ses réponses (REST API)
+    let thread_state = network_state.clone();
+    let thread = warp::path("api")
+        .and(warp::path("thread"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::any().map(move || thread_state.clone()))
+        .and_then(get_thread);
+
+    // Routes pour modifier ou supprimer un post du nœud (REST API)
+    let state = shared_state.clone();
+    let edit_post = warp::path("api")
+        .and(warp::path("post"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::put())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(state))
+        .and_then(update_post);
+
+    let state = shared_state.clone();
+    let delete_post = warp::path("api")
+        .and(warp::path("post"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
+        .and(with_state(state))
+        .and_then(remove_post);
+
+    // Route pour le fil global ou personnalisé d'une identité (REST API)
+    let feed_state = network_state.clone();
+    let feed = warp::path("api")
+        .and(warp::path("feed"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::query::<FeedQuery>())
+        .and(warp::any().map(move || feed_state.clone()))
+        .and_then(get_feed);
+
+    // Routes pour suivre ou ne plus suivre un auteur avec l'identité du nœud (REST API)
+    let state = shared_state.clone();
+    let follow = warp::path("api")
+        .and(warp::path("follow"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::post().map(|| true).or(warp::delete().map(|| false)).unify())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
+        .and(with_state(state))
+        .and_then(update_follow);
+
+    // Routes pour les canaux suivis par le nœud (REST API)
+    let channels_state = network_state.clone();
+    let channels = warp::path("api")
+        .and(warp::path("channels"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::any().map(move || channels_state.clone()))
+        .and_then(get_channels);
+
+    let channel_state = network_state.clone();
+    let update_channel = warp::path("api")
+        .and(warp::path("channels"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::post().map(|| true).or(warp::delete().map(|| false)).unify())
+        .and(operator_only())
+        .and(warp::any().map(move || channel_state.clone()))
+        .and_then(update_channel);
+
+    // Routes pour les messages directs du nœud local (REST API), déchiffrés
+    // avec sa clé : réservées à l'opérateur
+    let directs_state = network_state.clone();
+    let directs = warp::path("api")
+        .and(warp::path("direct"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(operator_only())
+        .and(warp::any().map(move || directs_state.clone()))
+        .and_then(get_directs);
+
+    let state = shared_state.clone();
+    let send_direct = warp::path("api")
+        .and(warp::path("direct"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(state))
+        .and_then(create_direct);
+
+    // Routes pour les fichiers joints (REST API). Un fichier présent est servi
+    // sans jeton : une page en affiche beaucoup. Une absence, qui déclenche
+    // des requêtes aux pairs, consomme un jeton de l'adresse du client.
+    let blob_state = network_state.clone();
+    let missing_blob_state = network_state.clone();
+    let get_blob = warp::path("api")
+        .and(warp::path("blobs"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::any().map(move || blob_state.clone()))
+        .and_then(get_blob)
+        .or(warp::path("api")
+            .and(warp::path("blobs"))
+            .and(warp::path::param::<String>())
+            .and(warp::path::end())
+            .and(warp::get())
+            .and(rate_limited(network_state.clone()))
+            .and(warp::any().map(move || missing_blob_state.clone()))
+            .and_then(fetch_missing_blob))
+        .unify();
+
+    let upload_state = network_state.clone();
+    let upload_blob = warp::path("api")
+        .and(warp::path("blobs"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(rate_limited(network_state.clone()))
+        .and(warp::query::<BlobQuery>())
+        .and(warp::body::content_length_limit(blob::MAX_BLOB_BYTES as u64))
+        .and(warp::body::bytes())
+        .and(warp::any().map(move || upload_state.clone()))
+        .and_then(upload_blob);
+
+    // Routes pour les profils (REST API)
+    let profiles_state = network_state.clone();
+    let profiles = warp::path("api")
+        .and(warp::path("profiles"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::any().map(move || profiles_state.clone()))
+        .and_then(get_profiles);
+
+    let profile_state = network_state.clone();
+    let profile = warp::path("api")
+        .and(warp::path("profile"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::any().map(move || profile_state.clone()))
+        .and_then(get_profile);
+
+    let state = shared_state.clone();
+    let set_profile = warp::path("api")
+        .and(warp::path("profile"))
+        .and(warp::path::end())
+        .and(warp::put())
+        .and(operator_only())
+        .and(rate_limited(network_state.clone()))
+        .and(warp::body::json())
+        .and(with_state(state))
+        .and_then(update_profile);
+
+    // Route pour l'annuaire des relais connus (REST API)
+    let relays_state = network_state.clone();
+    let relays = warp::path("api")
+        .and(warp::path("relays"))
+        .and(warp::get())
+        .and(warp::any().map(move || relays_state.clone()))
+        .and_then(get_relays);
+
+    // Route d'administration : état des limites de débit, depuis la machine locale seulement
+    let limits_state = network_state.clone();
+    let admin_limits = warp::path("api")
+        .and(warp::path("admin"))
+        .and(warp::path("limits"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(operator_only())
+        .and(warp::any().map(move || limits_state.clone()))
+        .and_then(get_limits);
+
     // Route WebSocket pour les clients navigateur
     let ws_state = network_state.clone();
     let ws_p2p_tx = shared_state.clone();
     let websocket = warp::path("ws")
         .and(warp::ws())
+        .and(client_ip(network_state.clone()))
+        .and(operator())
         .and(warp::any().map(move || ws_state.clone()))
         .and(warp::any().map(move || ws_p2p_tx.clone()))
-        .map(|ws: warp::ws::Ws, state: NetworkState, p2p_state: SharedState| {
-            ws.on_upgrade(move |socket| handle_websocket(socket, state, p2p_state))
+        .map(|ws: warp::ws::Ws, client_ip: Option<IpAddr>, operator: bool, state: NetworkState, p2p_state: SharedState| {
+            ws.on_upgrade(move |socket| handle_websocket(socket, client_ip, operator, state, p2p_state))
         });
 
     // Route pour servir le dossier pkg (WASM)
-    let pkg_files = warp::path("pkg").and(warp::fs::dir("./static/pkg"));
+    let pkg_files = warp::path("pkg").and(warp::fs::dir(static_dir.join("pkg")));
 
     // Combiner les routes
     let cors = warp::cors()
         .allow_any_origin()
-        .allow_methods(vec!["GET", "POST", "OPTIONS"])
+        .allow_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
         .allow_headers(vec!["Content-Type"]);
     
     let routes = websocket
         .or(pkg_files)
         .or(static_files)
         .or(network_info)
+        .or(relays)
+        .or(feed)
+        .or(follow)
+        .or(channels)
+        .or(update_channel)
+        .or(directs)
+        .or(send_direct)
+        .or(get_blob)
+        .or(upload_blob)
+        .or(profiles)
+        .or(profile)
+        .or(set_profile)
+        .or(thread)
+        .or(react)
+        .or(edit_post)
+        .or(delete_post)
         .or(post_message)
+        .or(admin_limits)
+        .recover(handle_rejection)
         .with(cors);
 
     tracing::info!("🌐 Serveur web démarré sur http://localhost:{}", port);
@@ -111,6 +407,121 @@ fn with_state(
     warp::any().map(move || state.clone())
 }
 
+impl warp::reject::Reject for Limited {}
+
+/// Adresse du client, derrière un éventuel proxy de confiance
+fn client_ip(network_state: NetworkState) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .map(move |remote: Option<SocketAddr>, forwarded_for: Option<String>| {
+            network_state.limits.client_ip(remote.map(|addr| addr.ip()), forwarded_for.as_deref())
+        })
+}
+
+/// Clés de limitation d'une publication ; une adresse inconnue n'est pas
+/// limitée par IP, un client sans identité prouvée pas par identité
+fn limit_keys<'a>(ip: Option<&'a str>, identity: Option<&'a str>, connection: Option<&'a str>) -> Vec<(LimitScope, &'a str)> {
+    let mut keys: Vec<(LimitScope, &str)> = identity.map(|identity| (LimitScope::Identity, identity)).into_iter().collect();
+    keys.extend(ip.map(|ip| (LimitScope::Ip, ip)));
+    keys.extend(connection.map(|connection| (LimitScope::Connection, connection)));
+    keys
+}
+
+/// Refuse la requête si l'adresse du client a épuisé ses jetons ; l'identité
+/// du nœud, partagée par tous les clients REST, n'est pas une clé
+fn rate_limited(network_state: NetworkState) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    client_ip(network_state.clone())
+        .and(warp::any().map(move || network_state.clone()))
+        .and_then(|ip: Option<IpAddr>, network_state: NetworkState| async move {
+            let ip = ip.map(|ip| ip.to_string());
+            network_state.limits
+                .check(&limit_keys(ip.as_deref(), None, None))
+                .map_err(|limited| {
+                    tracing::warn!("⏳ Requête REST limitée ({}) pour {}", limited.scope.as_str(), ip.as_deref().unwrap_or("?"));
+                    warp::reject::custom(limited)
+                })
+        })
+        .untuple_one()
+}
+
+/// Requête d'une route réservée à l'opérateur venue d'ailleurs
+#[derive(Debug)]
+struct NotOperator;
+
+impl warp::reject::Reject for NotOperator {}
+
+/// Indique si la requête vient de l'opérateur du nœud : requête directe
+/// depuis la machine locale, sans proxy, adressée à un hôte local et émise par
+/// une page locale s'il y a une origine (ni site tiers ni DNS rebinding)
+fn operator() -> impl Filter<Extract = (bool,), Error = Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::header::optional::<String>("x-forwarded-for"))
+        .and(warp::header::optional::<String>("host"))
+        .and(warp::header::optional::<String>("origin"))
+        .map(|remote: Option<SocketAddr>, forwarded_for: Option<String>, host: Option<String>, origin: Option<String>| {
+            is_operator(remote, forwarded_for.as_deref(), host.as_deref(), origin.as_deref())
+        })
+}
+
+/// Réserve une route à l'opérateur du nœud
+fn operator_only() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    operator()
+        .and_then(|operator: bool| async move {
+            if operator {
+                Ok(())
+            } else {
+                Err(warp::reject::custom(NotOperator))
+            }
+        })
+        .untuple_one()
+}
+
+fn is_operator(remote: Option<SocketAddr>, forwarded_for: Option<&str>, host: Option<&str>, origin: Option<&str>) -> bool {
+    // Une requête relayée par un proxy local vient en fait de l'extérieur
+    let local = remote.is_some_and(|addr| addr.ip().is_loopback()) && forwarded_for.is_none();
+    let local_origin = origin.is_none_or(|origin| {
+        origin
+            .strip_prefix("http://")
+            .or_else(|| origin.strip_prefix("https://"))
+            .is_some_and(is_loopback_host)
+    });
+    local && host.is_none_or(is_loopback_host) && local_origin
+}
+
+/// `localhost` ou une adresse de bouclage, avec ou sans port
+fn is_loopback_host(authority: &str) -> bool {
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Réponse 429 aux requêtes limitées et 403 aux routes réservées à
+/// l'opérateur, les autres refus gardent leur traitement par défaut
+async fn handle_rejection(rejection: Rejection) -> Result<warp::reply::Response, Rejection> {
+    if rejection.find::<NotOperator>().is_some() {
+        let reply = warp::reply::json(&serde_json::json!({ "error": "réservé à l'opérateur du nœud" }));
+        return Ok(warp::reply::with_status(reply, StatusCode::FORBIDDEN).into_response());
+    }
+    let Some(limited) = rejection.find::<Limited>() else {
+        return Err(rejection);
+    };
+    let retry_after_ms = limited.retry_after.as_millis() as u64;
+    let body = warp::reply::json(&serde_json::json!({
+        "error": "trop de publications",
+        "scope": limited.scope,
+        "retry_after_ms": retry_after_ms,
+    }));
+    let reply = warp::reply::with_status(body, StatusCode::TOO_MANY_REQUESTS);
+    let reply = warp::reply::with_header(reply, "Retry-After", retry_after_ms.div_ceil(1000).to_string());
+    Ok(reply.into_response())
+}
+
+async fn get_limits(network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    Ok(warp::reply::json(&network_state.limits.snapshot()))
+}
+
 async fn get_network_info(state: SharedState) -> Result<impl Reply, Rejection> {
     let state_guard = state.read().await;
     let (network_state, _, _, local_name, is_relay) = &*state_guard;
@@ -118,7 +529,8 @@ async fn get_network_info(state: SharedState) -> Result<impl Reply, Rejection> {
     let peers_map = network_state.peers.read().await;
     let peers: Vec<PeerInfo> = peers_map.values().cloned().collect();
 
-    let posts = network_state.posts.read().await.clone();
+    let posts = network_state.posts.read().await.all().to_vec();
+    let profiles = network_state.author_profiles(&posts).await;
 
     let info = NetworkInfo {
         local_peer_id: network_state.local_peer_id.to_string(),
@@ -126,50 +538,362 @@ async fn get_network_info(state: SharedState) -> Result<impl Reply, Rejection> {
         peers,
         posts,
         is_relay: *is_relay,
+        messages: network_state.message_stats.snapshot(),
+        reactions: network_state.reactions.read().await.all_counts(),
+        direct_messages: network_state.directs.read().await.len(),
+        profiles,
+        blobs: network_state.blobs.snapshot(),
+        peer_scores: network_state.peer_scores.read().await.clone(),
     };
 
     Ok(warp::reply::json(&info))
 }
 
+async fn get_relays(network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    let relays = network_state.known_relays().await;
+    Ok(warp::reply::json(&serde_json::json!({ "relays": relays })))
+}
+
+async fn get_thread(post_id: String, network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    Ok(match network_state.thread(&post_id).await {
+        Some(thread) => warp::reply::with_status(warp::reply::json(&thread), StatusCode::OK),
+        None => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": format!("post {} inconnu", post_id) })),
+            StatusCode::NOT_FOUND,
+        ),
+    })
+}
+
+async fn get_feed(query: FeedQuery, network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    let channel = query.channel.as_deref().and_then(channel::normalize);
+    let in_channel = |post: &Post| channel.as_deref().is_none_or(|c| post.channel() == c);
+    let reply = match query.identity {
+        Some(identity) => {
+            let following = network_state.follows.read().await.following(&identity);
+            let mut posts = network_state.home_feed(&identity).await;
+            posts.retain(in_channel);
+            let profiles = network_state.author_profiles(&posts).await;
+            serde_json::json!({ "mode": FeedMode::Following, "identity": identity, "following": following, "posts": posts, "profiles": profiles })
+        }
+        None => {
+            let posts: Vec<Post> = network_state.posts.read().await.all().iter().filter(|p| in_channel(p)).cloned().collect();
+            let profiles = network_state.author_profiles(&posts).await;
+            serde_json::json!({ "mode": FeedMode::Global, "posts": posts, "profiles": profiles })
+        }
+    };
+    Ok(warp::reply::json(&reply))
+}
+
+async fn update_follow(followee: String, following: bool, state: SharedState) -> Result<impl Reply, Rejection> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let follow = network_state.new_local_follow(followee, following);
+    let status = match network_state.ingest_follow(&follow, following).await {
+        Ingest::Added => {
+            let _ = ws_to_p2p_tx.send(crate::follows::follow_message(follow.clone(), following));
+            tracing::info!("{} Abonnement modifié via REST: {}", if following { "➕" } else { "➖" }, follow.followee);
+            StatusCode::OK
+        }
+        Ingest::Duplicate => StatusCode::CONFLICT,
+        // Un nœud ne peut pas se suivre lui-même
+        Ingest::Invalid => StatusCode::BAD_REQUEST,
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&follow), status))
+}
+
+async fn get_channels(network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    let channels = network_state.channels.read().await;
+    Ok(warp::reply::json(&serde_json::json!({
+        "channels": channels.local(),
+        // Canaux reçus pour le nœud ou pour ses navigateurs
+        "relayed": channels.wanted(),
+    })))
+}
+
+async fn update_channel(name: String, join: bool, network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    // Le canal par défaut est toujours suivi
+    let status = match channel::normalize(&name) {
+        Some(name) if name != channel::DEFAULT_CHANNEL => {
+            let changed = if join {
+                network_state.join_channel(&name).await
+            } else {
+                network_state.leave_channel(&name).await
+            };
+            if changed {
+                tracing::info!("{} Canal #{} {} via REST", if join { "➕" } else { "➖" }, name, if join { "suivi" } else { "quitté" });
+                StatusCode::OK
+            } else {
+                StatusCode::CONFLICT
+            }
+        }
+        _ => StatusCode::BAD_REQUEST,
+    };
+    let channels = network_state.channels.read().await.local();
+    Ok(warp::reply::with_status(warp::reply::json(&serde_json::json!({ "channels": channels })), status))
+}
+
+async fn get_directs(network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    let local = network_state.local_peer_id.to_string();
+    let messages: Vec<DirectView> = network_state
+        .directs
+        .read()
+        .await
+        .conversation(&local)
+        .iter()
+        .map(|message| DirectView {
+            sender: message.sender.clone(),
+            recipient: message.recipient.clone(),
+            timestamp: message.timestamp,
+            content: network_state.open_direct(message),
+        })
+        .collect();
+    Ok(warp::reply::json(&serde_json::json!({ "messages": messages })))
+}
+
+async fn create_direct(direct_req: DirectRequest, state: SharedState) -> Result<impl Reply, Rejection> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    // Destinataire sans clé ed25519 ou texte trop long
+    let Some(message) = network_state.new_local_direct(direct_req.recipient, &direct_req.content) else {
+        let reply = warp::reply::json(&serde_json::json!({ "error": "destinataire invalide ou message trop long" }));
+        return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST));
+    };
+    if network_state.ingest_direct(&message).await == Ingest::Added {
+        tracing::info!("✉️ Message direct envoyé via REST à {}", message.recipient);
+        let _ = ws_to_p2p_tx.send(NetworkMessage::Direct(message.clone()));
+    }
+
+    Ok(warp::reply::with_status(warp::reply::json(&message), StatusCode::OK))
+}
+
+/// Sert un fichier présent ; une absence passe à `fetch_missing_blob`
+async fn get_blob(cid: String, network_state: NetworkState) -> Result<warp::reply::Response, Rejection> {
+    if !blob::is_valid_cid(&cid) {
+        let reply = warp::reply::json(&serde_json::json!({ "error": "CID invalide" }));
+        return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST).into_response());
+    }
+    let Some((bytes, mime)) = network_state.blobs.get(&cid) else {
+        return Err(warp::reject::not_found());
+    };
+    // Le contenu d'un CID ne change jamais ; le type vient des octets, pas du client
+    let reply = warp::reply::with_header(bytes, "Content-Type", mime);
+    let reply = warp::reply::with_header(reply, "Cache-Control", "public, max-age=31536000, immutable");
+    let reply = warp::reply::with_header(reply, "X-Content-Type-Options", "nosniff");
+    Ok(reply.into_response())
+}
+
+/// Demande aux pairs un fichier absent, seulement s'il est joint à un post connu
+async fn fetch_missing_blob(cid: String, network_state: NetworkState) -> Result<warp::reply::Response, Rejection> {
+    let message = if network_state.is_attached(&cid).await {
+        network_state.want_blob(&cid).await;
+        format!("fichier {} absent, demandé aux pairs", cid)
+    } else {
+        format!("fichier {} inconnu", cid)
+    };
+    let reply = warp::reply::json(&serde_json::json!({ "error": message }));
+    Ok(warp::reply::with_status(reply, StatusCode::NOT_FOUND).into_response())
+}
+
+async fn upload_blob(query: BlobQuery, body: warp::hyper::body::Bytes, network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    let name = query.name.filter(|name| !name.is_empty());
+    if Attachment::describe(&body, name.clone()).is_some_and(|attachment| !attachment.is_valid()) {
+        let reply = warp::reply::json(&serde_json::json!({ "error": "nom de fichier invalide" }));
+        return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST));
+    }
+    let (reply, status) = match network_state.blobs.put(&body, name) {
+        Ok(attachment) => {
+            tracing::info!("📎 Fichier reçu via REST: {} ({}, {} octets)", attachment.cid, attachment.mime, attachment.size);
+            (serde_json::to_value(&attachment).unwrap_or_default(), StatusCode::OK)
+        }
+        Err(e) => {
+            let status = match e {
+                BlobError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                BlobError::Unsupported => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                BlobError::QuotaExceeded => StatusCode::INSUFFICIENT_STORAGE,
+                BlobError::Io(ref io) => {
+                    tracing::error!("❌ Erreur stockage du fichier: {}", io);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            (serde_json::json!({ "error": e.to_string() }), status)
+        }
+    };
+    Ok(warp::reply::with_status(warp::reply::json(&reply), status))
+}
+
+async fn get_profiles(network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    let profiles = network_state.profiles.read().await.all();
+    Ok(warp::reply::json(&serde_json::json!({ "profiles": profiles })))
+}
+
+async fn get_profile(identity: String, network_state: NetworkState) -> Result<impl Reply, Rejection> {
+    Ok(match network_state.profiles.read().await.get(&identity) {
+        Some(profile) => warp::reply::with_status(warp::reply::json(profile), StatusCode::OK),
+        None => warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": format!("aucun profil pour {}", identity) })),
+            StatusCode::NOT_FOUND,
+        ),
+    })
+}
+
+async fn update_profile(profile_req: ProfileRequest, state: SharedState) -> Result<impl Reply, Rejection> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let avatar = profile_req.avatar.filter(|avatar| !avatar.is_empty());
+    let profile = network_state.new_local_profile(profile_req.name, profile_req.bio, avatar);
+    let status = match network_state.ingest_profile(&profile).await {
+        Ingest::Added => {
+            let _ = ws_to_p2p_tx.send(NetworkMessage::Profile(profile.clone()));
+            tracing::info!("🪪 Profil modifié via REST: {}", profile.name);
+            StatusCode::OK
+        }
+        Ingest::Duplicate => StatusCode::CONFLICT,
+        // Nom vide ou trop long, bio trop longue, avatar invalide
+        Ingest::Invalid => StatusCode::BAD_REQUEST,
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&profile), status))
+}
+
+/// Code HTTP d'une modification ou suppression
+fn ingest_status(result: Ingest) -> StatusCode {
+    match result {
+        Ingest::Added => StatusCode::OK,
+        // Post inconnu ou modification déjà appliquée
+        Ingest::Duplicate => StatusCode::NOT_FOUND,
+        Ingest::Invalid => StatusCode::FORBIDDEN,
+    }
+}
+
+async fn update_post(
+    post_id: String,
+    edit_req: EditRequest,
+    state: SharedState,
+) -> Result<impl Reply, Rejection> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let edit = network_state.new_local_edit(post_id, edit_req.content);
+    let result = network_state.ingest_edit(edit.clone()).await;
+    if result == Ingest::Added {
+        tracing::info!("✏️ Post modifié via REST: {}", edit.post_id);
+        let _ = ws_to_p2p_tx.send(NetworkMessage::Edit(edit.clone()));
+    }
+
+    Ok(warp::reply::with_status(warp::reply::json(&edit), ingest_status(result)))
+}
+
+async fn remove_post(post_id: String, state: SharedState) -> Result<impl Reply, Rejection> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    // Seuls les posts du nœud peuvent être supprimés avec sa clé
+    let own_post = network_state
+        .posts
+        .read()
+        .await
+        .get(&post_id)
+        .is_some_and(|post| post.author == network_state.local_peer_id.to_string());
+    if !own_post {
+        let reply = warp::reply::json(&serde_json::json!({ "error": format!("post {} inconnu ou d'un autre auteur", post_id) }));
+        return Ok(warp::reply::with_status(reply, StatusCode::NOT_FOUND));
+    }
+
+    let delete = network_state.new_local_delete(post_id);
+    let result = network_state.ingest_delete(delete.clone()).await;
+    if result == Ingest::Added {
+        tracing::info!("🗑️ Post supprimé via REST: {}", delete.post_id);
+        let _ = ws_to_p2p_tx.send(NetworkMessage::Delete(delete.clone()));
+    }
+
+    Ok(warp::reply::with_status(warp::reply::json(&delete), ingest_status(result)))
+}
+
+async fn create_reaction(
+    react_req: ReactRequest,
+    state: SharedState,
+) -> Result<impl Reply, Rejection> {
+    let state_guard = state.read().await;
+    let (network_state, _, ws_to_p2p_tx, _, _) = &*state_guard;
+
+    let reaction = network_state.new_local_reaction(react_req.post_id, react_req.emoji);
+    let status = match network_state.ingest_reaction(&reaction).await {
+        Ingest::Added => {
+            let _ = ws_to_p2p_tx.send(NetworkMessage::Reaction(reaction.clone()));
+            tracing::info!("{} Réaction créée via REST au post {}", reaction.emoji, reaction.post_id);
+            StatusCode::OK
+        }
+        // Post inconnu ou réaction déjà comptée
+        Ingest::Duplicate => StatusCode::CONFLICT,
+        Ingest::Invalid => StatusCode::BAD_REQUEST,
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&reaction), status))
+}
+
 async fn create_post(
     post_req: PostRequest,
     state: SharedState,
 ) -> Result<impl Reply, Rejection> {
-    use chrono::Utc;
-    use uuid::Uuid;
-
     let state_guard = state.read().await;
     let (network_state, post_tx, _, _, _) = &*state_guard;
 
-    let post = Post {
-        id: Uuid::new_v4().to_string(),
-        author: network_state.local_peer_id.to_string(),
-        author_name: post_req.author_name,
-        content: post_req.content,
-        timestamp: Utc::now().timestamp(),
+    let channel = match post_req.channel.as_deref().map(channel::normalize) {
+        Some(None) => {
+            let reply = warp::reply::json(&serde_json::json!({ "error": "nom de canal invalide" }));
+            return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST));
+        }
+        Some(channel) => channel,
+        None => None,
+    };
+    if zeta_proto::grapheme_count(&post_req.content) > MAX_POST_GRAPHEMES {
+        let reply = warp::reply::json(&serde_json::json!({ "error": format!("post de plus de {} caractères", MAX_POST_GRAPHEMES) }));
+        return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST));
+    }
+    let Some(attachments) = network_state.local_attachments(post_req.attachments) else {
+        let reply = warp::reply::json(&serde_json::json!({ "error": "pièce jointe inconnue du relay ou trop nombreuses" }));
+        return Ok(warp::reply::with_status(reply, StatusCode::BAD_REQUEST));
     };
+    let post = network_state.new_local_post(post_req.author_name, post_req.content, post_req.reply_to, channel, attachments).await;
 
     // Envoyer au swarm pour diffusion P2P
     if let Err(e) = post_tx.send(post.clone()) {
         tracing::error!("❌ Erreur envoi post au swarm: {}", e);
     }
 
-    tracing::info!("📝 Post créé via REST: {} - {}", post.author_name, post.content);
+    tracing::info!("📝 Post créé via REST dans #{}: {} - {}", post.channel(), post.author_name, post.content);
 
-    Ok(warp::reply::json(&post))
+    Ok(warp::reply::with_status(warp::reply::json(&post), StatusCode::OK))
 }
 
 /// Gestion d'une connexion WebSocket
 async fn handle_websocket(
     ws: WebSocket,
+    client_ip: Option<IpAddr>,
+    operator: bool,
     network_state: NetworkState,
     p2p_state: SharedState,
 ) {
     let (mut ws_tx, mut ws_rx) = ws.split();
     
-    // Générer un ID unique pour ce client navigateur
-    let browser_peer_id = format!("browser-{}", &uuid::Uuid::new_v4().to_string()[..8]);
-    
+    // Générer un ID temporaire, remplacé par le vrai PeerId après identification
+    let mut browser_peer_id = format!("browser-{}", &uuid::Uuid::new_v4().to_string()[..8]);
+    // Clés de limitation propres à la connexion
+    let connection_id = browser_peer_id.clone();
+    let client_ip = client_ip.map(|ip| ip.to_string());
+    // Défi à signer par le client pour prouver son identité
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let mut authenticated = false;
+    // Fil diffusé sur cette connexion
+    let mut feed_mode = FeedMode::Global;
+    // Canaux reçus sur cette connexion
+    let mut channels = BTreeSet::from([channel::DEFAULT_CHANNEL.to_string()]);
+    network_state.browser_join_channel(channel::DEFAULT_CHANNEL).await;
+
     tracing::info!("🌐 Nouveau client WebSocket connecté: {}", browser_peer_id);
 
     // Ajouter ce client aux peers
@@ -178,6 +902,7 @@ async fn handle_websocket(
         address: "websocket".to_string(),
         name: Some("Navigateur".to_string()),
         is_browser: true,
+        public_key: None,
     }).await;
 
     // S'abonner aux broadcasts
@@ -185,17 +910,23 @@ async fn handle_websocket(
 
     // Envoyer l'état initial
     let initial_state = {
+        let relays = network_state.known_relays().await;
+        let reactions = network_state.reactions.read().await.all_counts();
+        let profiles = network_state.profiles.read().await.all();
         let peers = network_state.peers.read().await;
         let posts = network_state.posts.read().await;
-        serde_json::json!({
-            "type": "init",
-            "peer_id": browser_peer_id,
-            "peers": peers.values().collect::<Vec<_>>(),
-            "posts": posts.clone()
-        })
+        ServerFrame::Init {
+            peer_id: browser_peer_id.clone(),
+            nonce: nonce.clone(),
+            peers: peers.values().cloned().collect(),
+            posts: posts.all().iter().filter(|p| channels.contains(p.channel())).cloned().collect(),
+            relays,
+            reactions,
+            profiles,
+        }
     };
     
-    if ws_tx.send(Message::text(initial_state.to_string())).await.is_err() {
+    if ws_tx.send(Message::text(initial_state.to_json())).await.is_err() {
         tracing::error!("❌ Erreur envoi état initial");
         return;
     }
@@ -210,51 +941,356 @@ async fn handle_websocket(
                         if msg.is_text() {
                             if let Ok(text) = msg.to_str() {
                                 tracing::debug!("📥 Message WebSocket reçu: {}", text);
-                                if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(text) {
-                                    match ws_msg.msg_type.as_str() {
-                                        "post" => {
-                                            if let (Some(content), Some(author_name)) = (ws_msg.content, ws_msg.author_name) {
-                                                let post = Post {
-                                                    id: uuid::Uuid::new_v4().to_string(),
-                                                    author: browser_peer_id.clone(),
-                                                    author_name: author_name.clone(),
-                                                    content: content.clone(),
-                                                    timestamp: chrono::Utc::now().timestamp(),
-                                                };
-                                                
-                                                // Ajouter aux posts locaux
-                                                {
-                                                    let mut posts = network_state.posts.write().await;
-                                                    posts.insert(0, post.clone());
-                                                    if posts.len() > 100 {
-                                                        posts.truncate(100);
+                                let frame = match serde_json::from_str::<ClientFrame>(text) {
+                                    Ok(frame) => frame,
+                                    Err(e) => {
+                                        tracing::debug!("⚠️ Message WebSocket invalide ({}): {}", browser_peer_id, e);
+                                        let reply = ServerFrame::error(ErrorCode::InvalidFrame, e.to_string());
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                        continue;
+                                    }
+                                };
+                                if frame.publishes() {
+                                    // Un client sans identité ne peut publier que des posts non
+                                    // signés, réservés à l'opérateur : il n'est limité que par
+                                    // connexion et par IP
+                                    let identity = authenticated.then_some(browser_peer_id.as_str());
+                                    let keys = limit_keys(client_ip.as_deref(), identity, Some(&connection_id));
+                                    if let Err(limited) = network_state.limits.check(&keys) {
+                                        tracing::warn!("⏳ Client WebSocket {} limité ({})", browser_peer_id, limited.scope.as_str());
+                                        let reply = ServerFrame::RateLimited {
+                                            scope: limited.scope,
+                                            retry_after_ms: limited.retry_after.as_millis() as u64,
+                                        };
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                        continue;
+                                    }
+                                }
+                                match frame {
+                                    ClientFrame::Post(payload) => {
+                                        // Post signé par le client, sinon le relay signe pour son opérateur
+                                        let post = match payload {
+                                            PostPayload::Signed { post } => post,
+                                            PostPayload::Unsigned { .. } if !operator || authenticated => {
+                                                // Un client authentifié signe avec sa propre identité, et
+                                                // seul l'opérateur publie avec celle du relay
+                                                tracing::warn!("🚫 Post non signé refusé ({})", browser_peer_id);
+                                                let reply = ServerFrame::error(
+                                                    ErrorCode::SignatureRequired,
+                                                    "post non signé refusé : signez-le avec votre identité",
+                                                );
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                                continue;
+                                            }
+                                            PostPayload::Unsigned { content, author_name, reply_to, channel, attachments } => {
+                                                let channel = match channel.as_deref().map(channel::normalize) {
+                                                    Some(None) => {
+                                                        let reply = ServerFrame::error(ErrorCode::InvalidChannel, "nom de canal invalide");
+                                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                                        continue;
                                                     }
-                                                }
-                                                
-                                                // Broadcast à tous les clients WebSocket
-                                                let broadcast_msg = serde_json::json!({
-                                                    "type": "new_post",
-                                                    "post": post
-                                                }).to_string();
-                                                let _ = network_state.ws_broadcast.send(broadcast_msg);
-                                                
-                                                // Relayer au réseau P2P
+                                                    Some(channel) => channel,
+                                                    None => None,
+                                                };
+                                                let Some(attachments) = network_state.local_attachments(attachments) else {
+                                                    let reply = ServerFrame::error(ErrorCode::InvalidAttachment, "pièce jointe inconnue du relay ou trop nombreuses");
+                                                    let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                                    continue;
+                                                };
+                                                network_state.new_local_post(author_name, content, reply_to, channel, attachments).await
+                                            }
+                                        };
+
+                                        // Un post signé par le client doit venir de l'identité authentifiée
+                                        let is_local = post.author == network_state.local_peer_id.to_string();
+                                        if !is_local && (!authenticated || post.author != browser_peer_id) {
+                                            tracing::warn!("🚫 Post WebSocket d'une identité non authentifiée ({}): {}", browser_peer_id, post.author);
+                                            let reply = ServerFrame::error(
+                                                ErrorCode::Unauthenticated,
+                                                format!("l'auteur {} n'est pas l'identité authentifiée", post.author),
+                                            );
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        // Mêmes contrôles que pour un post reçu par gossipsub
+                                        if let Err(rejection) = validation::check(&NetworkMessage::Post(post.clone()), None, chrono::Utc::now().timestamp()) {
+                                            tracing::warn!("🚫 Post WebSocket rejeté ({}): {}", browser_peer_id, rejection);
+                                            let reply = ServerFrame::error(ErrorCode::InvalidPost, format!("post {} refusé: {}", post.id, rejection));
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        // Ajouter aux posts locaux (diffusion WebSocket incluse)
+                                        match network_state.ingest_post(post.clone()).await {
+                                            Ingest::Added => {}
+                                            Ingest::Duplicate => {
+                                                tracing::debug!("⚠️ Post WebSocket en double ignoré: {}", post.id);
+                                                let reply = ServerFrame::error(ErrorCode::DuplicatePost, format!("post {} déjà connu", post.id));
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                                continue;
+                                            }
+                                            Ingest::Invalid => {
+                                                tracing::warn!("🚫 Post WebSocket falsifié rejeté ({}): {}", browser_peer_id, post.id);
+                                                let reply = ServerFrame::error(ErrorCode::InvalidPost, format!("signature du post {} invalide", post.id));
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                                continue;
+                                            }
+                                        }
+
+                                        // Relayer au réseau P2P
+                                        let state_guard = p2p_state.read().await;
+                                        let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                        let _ = ws_to_p2p_tx.send(NetworkMessage::Post(post.clone()));
+
+                                        tracing::info!("📝 Post WebSocket relayé: {} - {}", post.author_name, post.content);
+                                    }
+                                    ClientFrame::Identify { peer_id, public_key, signature } => {
+                                        if authenticated {
+                                            tracing::debug!("⚠️ Client déjà authentifié: {}", browser_peer_id);
+                                            let reply = ServerFrame::error(ErrorCode::AlreadyIdentified, "client déjà authentifié");
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        if !verify_identity(&peer_id, &public_key, &signature, &nonce) {
+                                            tracing::warn!("🚫 Preuve d'identité invalide ({}): {}", browser_peer_id, peer_id);
+                                            let reply = ServerFrame::error(ErrorCode::InvalidIdentity, "signature du nonce invalide");
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        // Remplacer l'ID temporaire par le PeerId prouvé
+                                        network_state.remove_peer(&browser_peer_id).await;
+                                        browser_peer_id = peer_id;
+                                        authenticated = true;
+                                        network_state.add_peer(PeerInfo {
+                                            peer_id: browser_peer_id.clone(),
+                                            address: "websocket".to_string(),
+                                            name: Some("Navigateur".to_string()),
+                                            is_browser: true,
+                                            public_key: Some(public_key),
+                                        }).await;
+
+                                        let reply = ServerFrame::Identified { peer_id: browser_peer_id.clone() };
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                        let following = network_state.follows.read().await.following(&browser_peer_id);
+                                        let reply = ServerFrame::Following { identity: browser_peer_id.clone(), following };
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                        // Remettre les messages directs reçus en son absence
+                                        let conversation = network_state.directs.read().await.conversation(&browser_peer_id);
+                                        for message in conversation {
+                                            let _ = ws_tx.send(Message::text(ServerFrame::Direct { message }.to_json())).await;
+                                        }
+                                        tracing::info!("🆔 Client WebSocket identifié: {}", browser_peer_id);
+                                    }
+                                    ClientFrame::GetThread { post_id } => {
+                                        let reply = match network_state.thread(&post_id).await {
+                                            Some(thread) => ServerFrame::Thread { thread },
+                                            None => ServerFrame::error(ErrorCode::UnknownPost, format!("post {} inconnu", post_id)),
+                                        };
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                    }
+                                    ClientFrame::React { reaction } => {
+                                        // Une réaction doit venir de l'identité authentifiée
+                                        if !authenticated || reaction.author != browser_peer_id {
+                                            tracing::warn!("🚫 Réaction WebSocket d'une identité non authentifiée ({}): {}", browser_peer_id, reaction.author);
+                                            let reply = ServerFrame::error(
+                                                ErrorCode::Unauthenticated,
+                                                format!("l'auteur {} n'est pas l'identité authentifiée", reaction.author),
+                                            );
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        match network_state.ingest_reaction(&reaction).await {
+                                            Ingest::Added => {
                                                 let state_guard = p2p_state.read().await;
                                                 let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
-                                                let _ = ws_to_p2p_tx.send(NetworkMessage::Post(post.clone()));
-                                                
-                                                tracing::info!("📝 Post WebSocket relayé: {} - {}", author_name, content);
+                                                let _ = ws_to_p2p_tx.send(NetworkMessage::Reaction(reaction));
+                                            }
+                                            Ingest::Duplicate => {
+                                                tracing::debug!("⚠️ Réaction WebSocket déjà comptée ou post inconnu: {}", reaction.post_id);
+                                            }
+                                            Ingest::Invalid => {
+                                                let reply = ServerFrame::error(ErrorCode::InvalidReaction, "réaction mal formée ou signature invalide");
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
                                             }
                                         }
-                                        "ping" => {
-                                            let _ = ws_tx.send(Message::text(r#"{"type":"pong"}"#)).await;
+                                    }
+                                    ClientFrame::Edit { edit } => {
+                                        if !authenticated || edit.author != browser_peer_id {
+                                            let reply = ServerFrame::error(
+                                                ErrorCode::Unauthenticated,
+                                                format!("l'auteur {} n'est pas l'identité authentifiée", edit.author),
+                                            );
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
                                         }
-                                        _ => {
-                                            tracing::debug!("⚠️ Type de message inconnu: {}", ws_msg.msg_type);
+
+                                        let post_id = edit.post_id.clone();
+                                        if network_state.ingest_edit(edit.clone()).await == Ingest::Added {
+                                            let state_guard = p2p_state.read().await;
+                                            let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                            let _ = ws_to_p2p_tx.send(NetworkMessage::Edit(edit));
+                                        } else {
+                                            let reply = ServerFrame::error(ErrorCode::InvalidEdit, format!("modification du post {} refusée", post_id));
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
                                         }
                                     }
-                                } else {
-                                    tracing::warn!("⚠️ Message WebSocket invalide: {}", text);
+                                    ClientFrame::Delete { delete } => {
+                                        if !authenticated || delete.author != browser_peer_id {
+                                            let reply = ServerFrame::error(
+                                                ErrorCode::Unauthenticated,
+                                                format!("l'auteur {} n'est pas l'identité authentifiée", delete.author),
+                                            );
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        let post_id = delete.post_id.clone();
+                                        if network_state.ingest_delete(delete.clone()).await == Ingest::Added {
+                                            let state_guard = p2p_state.read().await;
+                                            let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                            let _ = ws_to_p2p_tx.send(NetworkMessage::Delete(delete));
+                                        } else {
+                                            let reply = ServerFrame::error(ErrorCode::InvalidEdit, format!("suppression du post {} refusée", post_id));
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                        }
+                                    }
+                                    ClientFrame::Follow { follow } | ClientFrame::Unfollow { follow } if !authenticated || follow.follower != browser_peer_id => {
+                                        let reply = ServerFrame::error(
+                                            ErrorCode::Unauthenticated,
+                                            format!("l'abonné {} n'est pas l'identité authentifiée", follow.follower),
+                                        );
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                    }
+                                    ClientFrame::Follow { follow } => {
+                                        match network_state.ingest_follow(&follow, true).await {
+                                            Ingest::Added => {
+                                                let state_guard = p2p_state.read().await;
+                                                let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                                let _ = ws_to_p2p_tx.send(NetworkMessage::Follow(follow));
+                                            }
+                                            Ingest::Duplicate => {}
+                                            Ingest::Invalid => {
+                                                let reply = ServerFrame::error(ErrorCode::InvalidFollow, "abonnement à signature invalide");
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            }
+                                        }
+                                    }
+                                    ClientFrame::Unfollow { follow } => {
+                                        match network_state.ingest_follow(&follow, false).await {
+                                            Ingest::Added => {
+                                                let state_guard = p2p_state.read().await;
+                                                let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                                let _ = ws_to_p2p_tx.send(NetworkMessage::Unfollow(follow));
+                                            }
+                                            Ingest::Duplicate => {}
+                                            Ingest::Invalid => {
+                                                let reply = ServerFrame::error(ErrorCode::InvalidFollow, "désabonnement à signature invalide");
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            }
+                                        }
+                                    }
+                                    ClientFrame::Direct { message } => {
+                                        if !authenticated || message.sender != browser_peer_id {
+                                            let reply = ServerFrame::error(
+                                                ErrorCode::Unauthenticated,
+                                                format!("l'expéditeur {} n'est pas l'identité authentifiée", message.sender),
+                                            );
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        match network_state.ingest_direct(&message).await {
+                                            Ingest::Added => {
+                                                let state_guard = p2p_state.read().await;
+                                                let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                                let _ = ws_to_p2p_tx.send(NetworkMessage::Direct(message));
+                                            }
+                                            Ingest::Duplicate => {}
+                                            Ingest::Invalid => {
+                                                let reply = ServerFrame::error(ErrorCode::InvalidDirect, "message direct mal formé ou signature invalide");
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            }
+                                        }
+                                    }
+                                    ClientFrame::SetProfile { profile } => {
+                                        if !authenticated || profile.identity != browser_peer_id {
+                                            let reply = ServerFrame::error(
+                                                ErrorCode::Unauthenticated,
+                                                format!("le profil de {} n'est pas celui de l'identité authentifiée", profile.identity),
+                                            );
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+
+                                        match network_state.ingest_profile(&profile).await {
+                                            Ingest::Added => {
+                                                tracing::info!("🪪 Profil WebSocket de {}: {}", browser_peer_id, profile.name);
+                                                let state_guard = p2p_state.read().await;
+                                                let (_, _, ws_to_p2p_tx, _, _) = &*state_guard;
+                                                let _ = ws_to_p2p_tx.send(NetworkMessage::Profile(profile));
+                                            }
+                                            Ingest::Duplicate => {}
+                                            Ingest::Invalid => {
+                                                let reply = ServerFrame::error(ErrorCode::InvalidProfile, "profil mal formé ou signature invalide");
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            }
+                                        }
+                                    }
+                                    ClientFrame::Subscribe { mode } => {
+                                        // Le fil personnalisé est celui de l'identité prouvée
+                                        if mode == FeedMode::Following && !authenticated {
+                                            let reply = ServerFrame::error(ErrorCode::Unauthenticated, "identify requis pour le fil des abonnements");
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        }
+                                        feed_mode = mode;
+                                        let mut posts = match mode {
+                                            FeedMode::Global => network_state.posts.read().await.all().to_vec(),
+                                            FeedMode::Following => network_state.home_feed(&browser_peer_id).await,
+                                        };
+                                        posts.retain(|p| channels.contains(p.channel()));
+                                        let _ = ws_tx.send(Message::text(ServerFrame::Feed { mode, posts }.to_json())).await;
+                                    }
+                                    ClientFrame::JoinChannel { channel: name } => {
+                                        let Some(name) = channel::normalize(&name) else {
+                                            let reply = ServerFrame::error(ErrorCode::InvalidChannel, format!("nom de canal invalide: {}", name));
+                                            let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                            continue;
+                                        };
+                                        if !channels.contains(&name) {
+                                            if channels.len() >= MAX_CHANNELS_PER_CONNECTION || !network_state.browser_join_channel(&name).await {
+                                                let reply = ServerFrame::error(ErrorCode::TooManyChannels, format!("trop de canaux suivis pour rejoindre #{}", name));
+                                                let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                                continue;
+                                            }
+                                            channels.insert(name.clone());
+                                            tracing::debug!("➕ {} suit le canal #{}", browser_peer_id, name);
+                                        }
+                                        let mut posts = network_state.channel_posts(&name).await;
+                                        if feed_mode == FeedMode::Following {
+                                            let follows = network_state.follows.read().await;
+                                            posts.retain(|p| p.author == browser_peer_id || follows.is_following(&browser_peer_id, &p.author));
+                                        }
+                                        let _ = ws_tx.send(Message::text(ServerFrame::Channel { channel: name, posts }.to_json())).await;
+                                        let reply = ServerFrame::Channels { channels: channels.iter().cloned().collect() };
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                    }
+                                    ClientFrame::LeaveChannel { channel: name } => {
+                                        let name = channel::normalize(&name).unwrap_or(name);
+                                        if channels.remove(&name) {
+                                            network_state.browser_leave_channel(&name).await;
+                                            tracing::debug!("➖ {} quitte le canal #{}", browser_peer_id, name);
+                                        }
+                                        let reply = ServerFrame::Channels { channels: channels.iter().cloned().collect() };
+                                        let _ = ws_tx.send(Message::text(reply.to_json())).await;
+                                    }
+                                    ClientFrame::Ping => {
+                                        let _ = ws_tx.send(Message::text(ServerFrame::Pong.to_json())).await;
+                                    }
                                 }
                             }
                         } else if msg.is_close() {
@@ -274,12 +1310,51 @@ async fn handle_websocket(
             // Broadcast depuis le réseau P2P
             broadcast = broadcast_rx.recv() => {
                 match broadcast {
-                    Ok(msg) => {
-                        if ws_tx.send(Message::text(msg)).await.is_err() {
+                    Ok(mut frame) => {
+                        // Un lot de posts synchronisés est filtré post par post
+                        if let ServerFrame::NewPosts { posts, .. } = &mut frame {
+                            posts.retain(|p| channels.contains(p.channel()));
+                            if feed_mode == FeedMode::Following {
+                                let follows = network_state.follows.read().await;
+                                posts.retain(|p| p.author == browser_peer_id || follows.is_following(&browser_peer_id, &p.author));
+                            }
+                        }
+                        let wanted = match &frame {
+                            ServerFrame::NewPosts { posts, reactions } => !posts.is_empty() || !reactions.is_empty(),
+                            // Seuls les canaux demandés sont relayés au navigateur
+                            ServerFrame::NewPost { post } | ServerFrame::PostUpdated { post } if !channels.contains(post.channel()) => false,
+                            ServerFrame::NewPost { post } | ServerFrame::PostUpdated { post } if feed_mode == FeedMode::Following => {
+                                network_state.in_home_feed(&browser_peer_id, post).await
+                            }
+                            // Seul l'abonné reçoit sa liste d'abonnements
+                            ServerFrame::Following { identity, .. } => authenticated && *identity == browser_peer_id,
+                            // Un message direct ne va qu'à son expéditeur et son destinataire
+                            ServerFrame::Direct { message } => {
+                                authenticated && (message.recipient == browser_peer_id || message.sender == browser_peer_id)
+                            }
+                            _ => true,
+                        };
+                        if !wanted {
+                            continue;
+                        }
+                        if ws_tx.send(Message::text(frame.to_json())).await.is_err() {
                             break;
                         }
                     }
-                    Err(_) => {
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        // Trames perdues : renvoyer le fil complet plutôt que couper la connexion
+                        tracing::warn!("⚠️ Client WebSocket {} en retard de {} trame(s), fil renvoyé", browser_peer_id, missed);
+                        let mut posts = match feed_mode {
+                            FeedMode::Global => network_state.posts.read().await.all().to_vec(),
+                            FeedMode::Following => network_state.home_feed(&browser_peer_id).await,
+                        };
+                        posts.retain(|p| channels.contains(p.channel()));
+                        let reply = ServerFrame::Feed { mode: feed_mode, posts };
+                        if ws_tx.send(Message::text(reply.to_json())).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
                         // Channel fermé
                         break;
                     }
@@ -288,7 +1363,62 @@ async fn handle_websocket(
         }
     }
 
-    // Retirer ce client
+    // Retirer ce client et ses canaux
+    for name in &channels {
+        network_state.browser_leave_channel(name).await;
+    }
     network_state.remove_peer(&browser_peer_id).await;
+    network_state.limits.forget(LimitScope::Connection, &connection_id);
     tracing::info!("👋 Client WebSocket déconnecté: {}", browser_peer_id);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(ip: &str) -> Option<SocketAddr> {
+        Some(SocketAddr::new(ip.parse().unwrap(), 50000))
+    }
+
+    #[test]
+    fn operator_is_a_direct_local_request() {
+        assert!(is_operator(addr("127.0.0.1"), None, Some("localhost:3030"), None));
+        assert!(is_operator(addr("::1"), None, Some("[::1]:3030"), Some("http://[::1]:3030")));
+        assert!(is_operator(addr("127.0.0.1"), None, Some("127.0.0.1:3030"), Some("http://localhost:3030")));
+    }
+
+    #[test]
+    fn remote_or_proxied_requests_are_not_the_operator() {
+        assert!(!is_operator(addr("203.0.113.7"), None, Some("localhost:3030"), None));
+        assert!(!is_operator(None, None, None, None));
+        // Un proxy local relaie des requêtes extérieures
+        assert!(!is_operator(addr("127.0.0.1"), Some("203.0.113.7"), Some("localhost:3030"), None));
+    }
+
+    #[test]
+    fn foreign_pages_are_not_the_operator() {
+        // Site tiers ouvert dans le navigateur de l'opérateur
+        assert!(!is_operator(addr("127.0.0.1"), None, Some("localhost:3030"), Some("https://evil.example")));
+        assert!(!is_operator(addr("127.0.0.1"), None, Some("localhost:3030"), Some("null")));
+        // DNS rebinding : le nom d'un tiers résolu vers la machine locale
+        assert!(!is_operator(addr("127.0.0.1"), None, Some("evil.example:3030"), None));
+        assert!(!is_loopback_host("localhost.evil.example"));
+    }
+
+    #[tokio::test]
+    async fn operator_filter_reads_the_request() {
+        let local = warp::test::request()
+            .remote_addr("127.0.0.1:50000".parse().unwrap())
+            .header("host", "localhost:3030");
+        assert_eq!(local.filter(&operator()).await.ok(), Some(true));
+
+        // Un visiteur du relay ne publie pas avec son identité
+        let visitor = || {
+            warp::test::request()
+                .remote_addr("203.0.113.7:50000".parse().unwrap())
+                .header("host", "relay.example")
+        };
+        assert_eq!(visitor().filter(&operator()).await.ok(), Some(false));
+        assert!(!visitor().matches(&operator_only()).await);
+    }
+}
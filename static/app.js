@@ -7,6 +7,9 @@ let ws = null;
 let myPeerId = null;
 let posts = [];
 let peers = [];
+let reactions = {};
+// Profils vérifiés par identité : ils fournissent les noms affichés
+let profiles = {};
 let reconnectAttempts = 0;
 const MAX_RECONNECT_ATTEMPTS = 10;
 
@@ -118,6 +121,9 @@ function scheduleReconnect() {
     }
 }
 
+/**
+ * @param {import('./zeta_protocol').ServerFrame} data
+ */
 function handleWebSocketMessage(data) {
     console.log('📩 Message WS:', data.type);
     
@@ -125,21 +131,86 @@ function handleWebSocketMessage(data) {
         case 'init':
             // État initial reçu
             myPeerId = data.peer_id;
-            peers = data.peers || [];
-            posts = data.posts || [];
+            peers = data.peers;
+            posts = data.posts;
+            reactions = data.reactions;
+            profiles = Object.fromEntries((data.profiles || []).map(p => [p.identity, p]));
             updateUI();
             break;
             
         case 'new_post':
-            // Nouveau post reçu
-            if (data.post) {
-                // Éviter les doublons
-                if (!posts.find(p => p.id === data.post.id)) {
-                    posts.unshift(data.post);
-                    updatePostsFeed();
-                    showNotification(`📨 Nouveau post de ${data.post.author_name}`);
+            // Nouveau post reçu, en évitant les doublons
+            if (!posts.find(p => p.id === data.post.id)) {
+                posts.unshift(data.post);
+                updatePostsFeed();
+                showNotification(`📨 Nouveau post de ${authorName(data.post)}`);
+            }
+            break;
+            
+        case 'new_posts':
+            // Posts rattrapés par synchronisation, en un seul lot
+            for (const post of data.posts) {
+                if (!posts.find(p => p.id === post.id)) {
+                    posts.push(post);
+                }
+            }
+            posts.sort((a, b) => b.timestamp - a.timestamp);
+            Object.assign(reactions, data.reactions || {});
+            updatePostsFeed();
+            break;
+            
+        case 'reactions':
+            reactions[data.post_id] = data.counts;
+            updatePostsFeed();
+            break;
+            
+        case 'post_updated': {
+            const index = posts.findIndex(p => p.id === data.post.id);
+            if (index !== -1) {
+                posts[index] = data.post;
+                updatePostsFeed();
+            }
+            break;
+        }
+            
+        case 'post_deleted':
+            posts = posts.filter(p => p.id !== data.post_id);
+            updatePostsFeed();
+            break;
+            
+        case 'feed':
+            // Fil choisi avec `subscribe` (global ou abonnements)
+            posts = data.posts;
+            updatePostsFeed();
+            break;
+            
+        case 'following':
+            console.log('➕ Abonnements:', data.following.length);
+            break;
+            
+        case 'direct':
+            // Chiffré de bout en bout : seul le nœud WASM peut le lire
+            console.log('✉️ Message direct chiffré de', data.message.sender);
+            break;
+            
+        case 'channel':
+            // Posts déjà connus d'un canal rejoint
+            for (const post of data.posts) {
+                if (!posts.find(p => p.id === post.id)) {
+                    posts.push(post);
                 }
             }
+            posts.sort((a, b) => b.timestamp - a.timestamp);
+            updatePostsFeed();
+            break;
+            
+        case 'channels':
+            console.log('#️⃣ Canaux:', data.channels.join(', '));
+            break;
+            
+        case 'profile':
+            profiles[data.profile.identity] = data.profile;
+            updateUI();
             break;
             
         case 'peer_joined':
@@ -157,11 +228,24 @@ function handleWebSocketMessage(data) {
             // Réponse au ping
             break;
             
+        case 'error':
+            console.warn(`⚠️ Refus du relay (${data.code}):`, data.message);
+            showNotification(`⚠️ ${data.message}`, true);
+            break;
+            
+        case 'rate_limited':
+            console.warn(`⏳ Débit limité (${data.scope}), réessayer dans ${data.retry_after_ms} ms`);
+            showNotification(`⏳ Trop de publications, réessayez dans ${Math.ceil(data.retry_after_ms / 1000)} s`, true);
+            break;
+            
         default:
             console.log('Message inconnu:', data);
     }
 }
 
+/**
+ * @param {import('./zeta_protocol').ClientFrame} message
+ */
 function sendWebSocketMessage(message) {
     if (ws && ws.readyState === WebSocket.OPEN) {
         ws.send(JSON.stringify(message));
@@ -190,6 +274,9 @@ async function fetchNetworkInfoREST() {
         if (!ws || ws.readyState !== WebSocket.OPEN) {
             peers = data.peers || [];
             posts = data.posts || [];
+            for (const profile of data.profiles || []) {
+                profiles[profile.identity] = profile;
+            }
             updateUI();
         }
     } catch (error) {
@@ -270,12 +357,14 @@ function updatePostsFeed() {
     const html = posts.map(post => `
         <div class="post-item">
             <div class="post-header">
-                <span class="post-author">👤 ${escapeHtml(post.author_name || post.author)}</span>
+                <span class="post-author">👤 ${escapeHtml(authorName(post))}</span>
                 <span class="post-time">${formatTimestamp(post.timestamp)}</span>
             </div>
-            <div class="post-content">${escapeHtml(post.content)}</div>
+            <div class="post-content">${escapeHtml(post.edit ? post.edit.content : post.content)}${post.edit ? ' <span class="post-edited">(modifié)</span>' : ''}</div>
+            ${formatAttachments(post)}
             <div class="post-meta">
                 <span class="post-id">🔗 ${truncateId(post.author)}</span>
+                <span class="post-reactions">${formatReactions(reactions[post.id])}</span>
             </div>
         </div>
     `).join('');
@@ -284,6 +373,28 @@ function updatePostsFeed() {
     elements.postCountBadge.textContent = posts.length;
 }
 
+function formatReactions(counts) {
+    return Object.entries(counts || {})
+        .map(([emoji, count]) => `${escapeHtml(emoji)} ${count}`)
+        .join(' ');
+}
+
+// Nom du profil signé de l'auteur, sinon le nom déclaré dans le post
+function authorName(post) {
+    return profiles[post.author]?.name || post.author_name || post.author;
+}
+
+// Pièces jointes : images affichées, autres fichiers en lien
+function formatAttachments(post) {
+    return (post.attachments || []).map(a => {
+        const url = `/api/blobs/${encodeURIComponent(a.cid)}`;
+        const label = escapeHtml(a.name || a.cid.substring(0, 12));
+        return a.mime.startsWith('image/')
+            ? `<img class="post-attachment" src="${url}" alt="${label}" loading="lazy">`
+            : `<a class="post-attachment" href="${url}" target="_blank" rel="noopener">📎 ${label}</a>`;
+    }).join('');
+}
+
 function updateCharCount() {
     const count = elements.contentInput.value.length;
     elements.charCount.textContent = count;
@@ -333,12 +444,17 @@ async function handleSubmit(event) {
                 body: JSON.stringify({ content, author_name: authorName })
             });
 
-            if (!response.ok) throw new Error('Erreur publication');
+            if (response.status === 429) {
+                const retryAfter = response.headers.get('Retry-After') || '?';
+                showNotification(`⏳ Trop de publications, réessayez dans ${retryAfter} s`, true);
+            } else {
+                if (!response.ok) throw new Error('Erreur publication');
 
-            elements.contentInput.value = '';
-            elements.charCount.textContent = '0';
-            showNotification('✅ Post publié via REST !');
-            fetchNetworkInfoREST();
+                elements.contentInput.value = '';
+                elements.charCount.textContent = '0';
+                showNotification('✅ Post publié via REST !');
+                fetchNetworkInfoREST();
+            }
         } catch (error) {
             console.error('Erreur:', error);
             showNotification('❌ Erreur lors de la publication', true);
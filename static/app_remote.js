@@ -9,6 +9,8 @@ let myPeerId = null;
 let myName = null;
 let posts = [];
 let peers = [];
+// Profils vérifiés par identité : ils fournissent les noms affichés
+let profiles = {};
 let reconnectAttempts = 0;
 const MAX_RECONNECT_ATTEMPTS = 10;
 
@@ -125,6 +127,9 @@ function scheduleReconnect() {
 // Gestion des messages WebSocket
 // ============================================
 
+/**
+ * @param {import('./zeta_protocol').ServerFrame} data
+ */
 function handleWebSocketMessage(data) {
     console.log('📩 Message:', data.type);
     
@@ -132,51 +137,119 @@ function handleWebSocketMessage(data) {
         case 'init':
             // État initial reçu du relay
             myPeerId = data.peer_id;
-            peers = data.peers || [];
-            posts = data.posts || [];
+            peers = data.peers;
+            posts = data.posts;
+            profiles = Object.fromEntries((data.profiles || []).map(p => [p.identity, p]));
             console.log('🆔 Mon PeerId:', myPeerId);
             console.log('👥 Peers:', peers.length, '| 📝 Posts:', posts.length);
             updateUI();
             break;
             
-        case 'new_post':
-        case 'Post':
+        case 'new_post': {
             // Nouveau post reçu via GossipSub
-            const post = data.post || data.Post || data;
-            if (post && post.id) {
-                // Éviter les doublons
+            const post = data.post;
+            // Éviter les doublons
+            if (!posts.find(p => p.id === post.id)) {
+                posts.unshift(post);
+                updatePostsFeed();
+                
+                // Notification si ce n'est pas notre propre post
+                if (post.author !== myPeerId) {
+                    showNotification(`📨 ${authorName(post)}: ${post.content.substring(0, 50)}...`);
+                }
+            }
+            break;
+        }
+            
+        case 'new_posts':
+            // Posts rattrapés par synchronisation, en un seul lot
+            for (const post of data.posts) {
                 if (!posts.find(p => p.id === post.id)) {
-                    posts.unshift(post);
-                    updatePostsFeed();
-                    
-                    // Notification si ce n'est pas notre propre post
-                    if (post.author !== myPeerId) {
-                        showNotification(`📨 ${post.author_name}: ${post.content.substring(0, 50)}...`);
-                    }
+                    posts.push(post);
                 }
             }
+            posts.sort((a, b) => b.timestamp - a.timestamp);
+            updatePostsFeed();
+            break;
+            
+        case 'post_updated': {
+            const index = posts.findIndex(p => p.id === data.post.id);
+            if (index !== -1) {
+                posts[index] = data.post;
+                updatePostsFeed();
+            }
+            break;
+        }
+            
+        case 'post_deleted':
+            posts = posts.filter(p => p.id !== data.post_id);
+            updatePostsFeed();
+            break;
+            
+        case 'feed':
+            // Fil choisi avec `subscribe` (global ou abonnements)
+            posts = data.posts;
+            updatePostsFeed();
+            break;
+            
+        case 'following':
+            console.log('➕ Abonnements:', data.following.length);
+            break;
+            
+        case 'direct':
+            // Chiffré de bout en bout : seul le nœud WASM peut le lire
+            console.log('✉️ Message direct chiffré de', data.message.sender);
+            break;
+            
+        case 'channel':
+            // Posts déjà connus d'un canal rejoint
+            for (const post of data.posts) {
+                if (!posts.find(p => p.id === post.id)) {
+                    posts.push(post);
+                }
+            }
+            posts.sort((a, b) => b.timestamp - a.timestamp);
+            updatePostsFeed();
+            break;
+            
+        case 'channels':
+            console.log('#️⃣ Canaux:', data.channels.join(', '));
+            break;
+            
+        case 'profile':
+            profiles[data.profile.identity] = data.profile;
+            updateUI();
             break;
             
         case 'peer_joined':
-        case 'PeerJoined':
-            console.log('👋 Nouveau peer:', data.peer_id || data.PeerJoined?.peer_id);
+            console.log('👋 Nouveau peer:', data.peer_id);
             // Rafraîchir via REST ou attendre le prochain message
             break;
             
         case 'peer_left':
-        case 'PeerLeft':
-            console.log('👋 Peer parti:', data.peer_id || data.PeerLeft?.peer_id);
-            const leftId = data.peer_id || data.PeerLeft?.peer_id;
-            if (leftId) {
-                peers = peers.filter(p => p.peer_id !== leftId);
-                updatePeersList();
-            }
+            console.log('👋 Peer parti:', data.peer_id);
+            peers = peers.filter(p => p.peer_id !== data.peer_id);
+            updatePeersList();
+            break;
+            
+        case 'identified':
+            console.log('🆔 Identité reconnue:', data.peer_id);
             break;
             
         case 'pong':
             // Réponse au ping, connexion active
             break;
             
+        case 'error':
+            console.warn(`⚠️ Refus du relay (${data.code}):`, data.message);
+            showNotification(`⚠️ ${data.message}`);
+            break;
+            
+        case 'rate_limited':
+            console.warn(`⏳ Débit limité (${data.scope}), réessayer dans ${data.retry_after_ms} ms`);
+            showNotification(`⏳ Trop de publications, réessayez dans ${Math.ceil(data.retry_after_ms / 1000)} s`);
+            break;
+            
         default:
             console.log('Message inconnu:', data);
     }
@@ -207,7 +280,8 @@ async function handleSubmit(e) {
     }
     
     try {
-        // Envoyer via WebSocket
+        // Envoyer via WebSocket ; un relay distant refuse les posts non
+        // signés (erreur `signature_required`), à publier depuis le nœud WASM
         if (ws && ws.readyState === WebSocket.OPEN) {
             ws.send(JSON.stringify({
                 type: 'post',
@@ -224,8 +298,8 @@ async function handleSubmit(e) {
             updateCharCount();
             
         } else {
-            // Fallback: envoyer via REST API
-            await sendPostREST(author, content);
+            // Pas de repli REST : la publication y est réservée à l'opérateur du relay
+            throw new Error('non connecté au relay');
         }
         
     } catch (error) {
@@ -239,36 +313,6 @@ async function handleSubmit(e) {
     }
 }
 
-async function sendPostREST(author, content) {
-    const response = await fetch(`${config.api}/post`, {
-        method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
-        body: JSON.stringify({
-            author_name: author,
-            content: content
-        })
-    });
-    
-    if (!response.ok) {
-        throw new Error('Erreur serveur: ' + response.status);
-    }
-    
-    const post = await response.json();
-    console.log('📤 Post envoyé via REST:', post);
-    
-    // Ajouter localement
-    if (!posts.find(p => p.id === post.id)) {
-        posts.unshift(post);
-        updatePostsFeed();
-    }
-    
-    // Vider le champ
-    if (elements.contentInput) {
-        elements.contentInput.value = '';
-    }
-    updateCharCount();
-}
-
 // ============================================
 // Mise à jour de l'interface
 // ============================================
@@ -338,10 +382,11 @@ function updatePostsFeed() {
         return `
             <div class="post ${isLocal ? 'post-local' : ''}" data-id="${post.id}">
                 <div class="post-header">
-                    <span class="post-author">${escapeHtml(post.author_name)}</span>
+                    <span class="post-author">${escapeHtml(authorName(post))}</span>
                     <span class="post-time" title="${date.toLocaleString()}">${timeAgo}</span>
                 </div>
-                <div class="post-content">${escapeHtml(post.content)}</div>
+                <div class="post-content">${escapeHtml(post.edit ? post.edit.content : post.content)}${post.edit ? ' <span class="post-edited">(modifié)</span>' : ''}</div>
+                ${formatAttachments(post)}
                 <div class="post-footer">
                     <span class="post-peer-id" title="${post.author}">
                         ${isLocal ? '📍 Vous' : `🔗 ${post.author.substring(0, 12)}...`}
@@ -368,6 +413,22 @@ function updateConnectionStatus(status) {
     }
 }
 
+// Nom du profil signé de l'auteur, sinon le nom déclaré dans le post
+function authorName(post) {
+    return profiles[post.author]?.name || post.author_name;
+}
+
+// Pièces jointes : images affichées, autres fichiers en lien
+function formatAttachments(post) {
+    return (post.attachments || []).map(a => {
+        const url = `${config.api}/blobs/${encodeURIComponent(a.cid)}`;
+        const label = escapeHtml(a.name || a.cid.substring(0, 12));
+        return a.mime.startsWith('image/')
+            ? `<img class="post-attachment" src="${url}" alt="${label}" loading="lazy">`
+            : `<a class="post-attachment" href="${url}" target="_blank" rel="noopener">📎 ${label}</a>`;
+    }).join('');
+}
+
 function updateCharCount() {
     if (elements.charCount && elements.contentInput) {
         elements.charCount.textContent = elements.contentInput.value.length;
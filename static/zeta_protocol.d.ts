@@ -0,0 +1,168 @@
+// Généré par `cargo run -p zeta-proto --features typescript --bin ws-typings`, ne pas modifier.
+// Protocole WebSocket entre un relay Zeta2 et ses clients navigateur.
+
+export type Attachment = { 
+/**
+ * Empreinte SHA-256 (hex) du contenu
+ */
+cid: string, 
+/**
+ * Type MIME déduit du contenu
+ */
+mime: string, size: number, 
+/**
+ * Nom d'origine du fichier, purement indicatif
+ */
+name?: string, };
+
+export type Post = { id: string, author: string, author_name: string, content: string, timestamp: number, 
+/**
+ * Clé publique ed25519 de l'auteur (hex)
+ */
+public_key: string, 
+/**
+ * Signature ed25519 (hex) de l'encodage canonique du post
+ */
+signature: string, 
+/**
+ * Post auquel celui-ci répond
+ */
+reply_to?: string, 
+/**
+ * Premier post de la conversation
+ */
+thread_root?: string, 
+/**
+ * Dernière modification signée par l'auteur, hors signature du post
+ */
+edit?: PostEdit, 
+/**
+ * Canal du post, `general` s'il est absent
+ */
+channel?: string, 
+/**
+ * Fichiers joints, référencés par leur contenu
+ */
+attachments?: Array<Attachment>, };
+
+export type PostEdit = { post_id: string, 
+/**
+ * Nouveau texte du post
+ */
+content: string, 
+/**
+ * Date en millisecondes : deux modifications peuvent se suivre de près
+ */
+edited_at: number, 
+/**
+ * PeerId de l'auteur, qui contient sa clé publique
+ */
+author: string, signature: string, };
+
+export type PostDelete = { post_id: string, deleted_at: number, 
+/**
+ * PeerId de l'auteur, qui contient sa clé publique
+ */
+author: string, signature: string, };
+
+export type PeerInfo = { peer_id: string, address: string, name: string | null, is_browser: boolean, 
+/**
+ * Clé publique ed25519 (hex) vérifiée pour ce peer
+ */
+public_key: string | null, };
+
+export type RelayInfo = { peer_id: string, ws_url: string, last_seen: number, };
+
+export type ThreadNode = { post: Post, 
+/**
+ * Réponses directes, de la plus ancienne à la plus récente
+ */
+replies: Array<ThreadNode>, };
+
+export type Reaction = { post_id: string, emoji: string, 
+/**
+ * PeerId de l'auteur, qui contient sa clé publique
+ */
+author: string, 
+/**
+ * Signature ed25519 (hex) de l'encodage canonique de la réaction
+ */
+signature: string, };
+
+export type Follow = { 
+/**
+ * PeerId de l'abonné, qui contient sa clé publique
+ */
+follower: string, 
+/**
+ * PeerId de l'auteur suivi
+ */
+followee: string, 
+/**
+ * Date en millisecondes : un désabonnement peut suivre de près l'abonnement
+ */
+timestamp: number, signature: string, };
+
+export type FeedMode = "global" | "following";
+
+export type DirectMessage = { 
+/**
+ * PeerId de l'expéditeur, qui contient sa clé publique
+ */
+sender: string, 
+/**
+ * PeerId du destinataire, dont est dérivée la clé de chiffrement
+ */
+recipient: string, timestamp: number, 
+/**
+ * Nonce aléatoire (hex, 12 octets), unique par message
+ */
+nonce: string, 
+/**
+ * Texte chiffré et tag d'authentification (hex)
+ */
+ciphertext: string, 
+/**
+ * Signature ed25519 de l'expéditeur (hex)
+ */
+signature: string, };
+
+export type Profile = { 
+/**
+ * PeerId de l'identité, qui contient sa clé publique
+ */
+identity: string, name: string, bio: string, 
+/**
+ * Référence de l'image (URL ou identifiant de contenu)
+ */
+avatar?: string, 
+/**
+ * Date en millisecondes
+ */
+timestamp: number, signature: string, };
+
+export type ErrorCode = "invalid_frame" | "unauthenticated" | "invalid_post" | "duplicate_post" | "invalid_identity" | "already_identified" | "unknown_post" | "invalid_reaction" | "invalid_edit" | "invalid_follow" | "invalid_direct" | "invalid_channel" | "too_many_channels" | "invalid_attachment" | "invalid_profile" | "signature_required";
+
+export type LimitScope = "connection" | "ip" | "identity" | "peer";
+
+export type ServerFrame = { "type": "init", peer_id: string, nonce: string, peers: Array<PeerInfo>, posts: Array<Post>, relays: Array<RelayInfo>, 
+/**
+ * Réactions par post
+ */
+reactions: { [key in string]?: { [key in string]?: number } }, 
+/**
+ * Profils vérifiés connus du relay
+ */
+profiles: Array<Profile>, } | { "type": "new_post", post: Post, } | { "type": "new_posts", posts: Array<Post>, reactions: { [key in string]?: { [key in string]?: number } }, } | { "type": "post_updated", post: Post, } | { "type": "post_deleted", post_id: string, } | { "type": "reactions", post_id: string, counts: { [key in string]?: number }, } | { "type": "peer_joined", peer_id: string, } | { "type": "peer_left", peer_id: string, } | { "type": "identified", peer_id: string, } | { "type": "pong" } | { "type": "thread", thread: ThreadNode, } | { "type": "following", identity: string, following: Array<string>, } | { "type": "feed", mode: FeedMode, posts: Array<Post>, } | { "type": "channels", channels: Array<string>, } | { "type": "channel", channel: string, posts: Array<Post>, } | { "type": "direct", message: DirectMessage, } | { "type": "profile", profile: Profile, } | { "type": "error", code: ErrorCode, message: string, } | { "type": "rate_limited", scope: LimitScope, retry_after_ms: number, };
+
+export type PostPayload = { post: Post, } | { content: string, author_name: string, reply_to?: string, 
+/**
+ * Canal du post, celui du parent pour une réponse
+ */
+channel?: string, 
+/**
+ * Fichiers déjà envoyés au relay (`POST /api/blobs`)
+ */
+attachments?: Array<Attachment>, };
+
+export type ClientFrame = { "type": "post" } & PostPayload | { "type": "identify", peer_id: string, public_key: string, signature: string, } | { "type": "get_thread", post_id: string, } | { "type": "react", reaction: Reaction, } | { "type": "edit", edit: PostEdit, } | { "type": "delete", delete: PostDelete, } | { "type": "follow", follow: Follow, } | { "type": "unfollow", follow: Follow, } | { "type": "direct", message: DirectMessage, } | { "type": "set_profile", profile: Profile, } | { "type": "subscribe", mode: FeedMode, } | { "type": "join_channel", channel: string, } | { "type": "leave_channel", channel: string, } | { "type": "ping" };
@@ -3,48 +3,27 @@
 //! Ce module permet d'exécuter un nœud P2P directement dans le navigateur.
 //! Il utilise WebSocket pour se connecter au relay P2P.
 
-use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{MessageEvent, WebSocket};
+use zeta_proto::{blob, channel, signing};
+use zeta_proto::ws::{ClientFrame, FeedMode, PostPayload, ServerFrame};
 
 // ============================================
 // Structures de données
 // ============================================
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Post {
-    pub id: String,
-    pub author: String,
-    pub author_name: String,
-    pub content: String,
-    pub timestamp: i64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PeerInfo {
-    pub peer_id: String,
-    pub address: String,
-    pub name: Option<String>,
-    pub is_browser: bool,
-}
-
-#[derive(Serialize, Deserialize)]
-struct WsOutMessage {
-    #[serde(rename = "type")]
-    msg_type: String,
-    content: Option<String>,
-    author_name: Option<String>,
-}
+// Les types du protocole sont partagés avec le nœud natif
+pub use zeta_proto::{Attachment, DirectMessage, Follow, PeerInfo, Post, PostDelete, PostEdit, Profile, Reaction, ReactionCounts, ThreadNode};
 
 // ============================================
 // État global du nœud
 // ============================================
 
 thread_local! {
-    static NODE: RefCell<Option<ZetaNode>> = RefCell::new(None);
+    static NODE: RefCell<Option<ZetaNode>> = const { RefCell::new(None) };
 }
 
 struct ZetaNode {
@@ -52,11 +31,26 @@ struct ZetaNode {
     name: String,
     private_key: [u8; 32],
     ws: Option<WebSocket>,
+    // Relais connus (URL WebSocket) et index du relay courant
+    relay_urls: Vec<String>,
+    relay_index: usize,
     peers: HashMap<String, PeerInfo>,
     posts: Vec<Post>,
+    // Compteurs de réactions par post
+    reactions: HashMap<String, ReactionCounts>,
+    // Auteurs suivis et fil demandé au relay
+    following: Vec<String>,
+    feed_mode: FeedMode,
+    // Canaux demandés au relay, redemandés à chaque connexion
+    channels: Vec<String>,
+    // Messages directs déjà remis au JavaScript (par nonce)
+    directs_seen: HashSet<String>,
+    // Dernier profil vérifié de chaque identité
+    profiles: HashMap<String, Profile>,
     on_message: Option<js_sys::Function>,
     on_peers: Option<js_sys::Function>,
     on_status: Option<js_sys::Function>,
+    on_direct: Option<js_sys::Function>,
 }
 
 // ============================================
@@ -76,22 +70,33 @@ pub fn init(relay_url: Option<String>) -> Result<JsValue, JsValue> {
 
     // Générer ou charger la clé
     let (peer_id, private_key) = load_or_generate_identity()?;
-    let name = format!("Browser-{}", &peer_id[..8]);
+    let name = format!("Browser-{}", &peer_id[peer_id.len() - 8..]);
 
     log(&format!("🆔 PeerId: {}", peer_id));
     log(&format!("👤 Nom: {}", name));
 
+    let relay = relay_url.unwrap_or_else(|| "ws://65.75.201.11:3030/ws".to_string());
+
     // Créer le nœud
     let node = ZetaNode {
         peer_id: peer_id.clone(),
         name: name.clone(),
         private_key,
         ws: None,
+        relay_urls: vec![relay.clone()],
+        relay_index: 0,
         peers: HashMap::new(),
         posts: Vec::new(),
+        reactions: HashMap::new(),
+        following: Vec::new(),
+        feed_mode: FeedMode::Global,
+        channels: vec![channel::DEFAULT_CHANNEL.to_string()],
+        directs_seen: HashSet::new(),
+        profiles: HashMap::new(),
         on_message: None,
         on_peers: None,
         on_status: None,
+        on_direct: None,
     };
 
     NODE.with(|n| {
@@ -99,7 +104,6 @@ pub fn init(relay_url: Option<String>) -> Result<JsValue, JsValue> {
     });
 
     // Connexion au relay
-    let relay = relay_url.unwrap_or_else(|| "ws://65.75.201.11:3030/ws".to_string());
     connect_to_relay(&relay)?;
 
     // Retourner les infos
@@ -132,6 +136,19 @@ pub fn on_peers_change(callback: js_sys::Function) {
     });
 }
 
+/// Définit le callback des messages directs déchiffrés
+///
+/// Le callback reçoit `{sender, recipient, timestamp, content}`, y compris
+/// pour nos propres envois confirmés par le relay.
+#[wasm_bindgen]
+pub fn on_direct_message(callback: js_sys::Function) {
+    NODE.with(|n| {
+        if let Some(ref mut node) = *n.borrow_mut() {
+            node.on_direct = Some(callback);
+        }
+    });
+}
+
 /// Définit le callback pour les changements de statut
 #[wasm_bindgen]
 pub fn on_status_change(callback: js_sys::Function) {
@@ -142,32 +159,63 @@ pub fn on_status_change(callback: js_sys::Function) {
     });
 }
 
-/// Publie un post sur le réseau
+/// Publie un post sur le réseau, éventuellement en réponse à `reply_to`,
+/// dans `channel` (`general` par défaut, celui du parent pour une réponse),
+/// avec les fichiers déjà envoyés au relay (réponses de `POST /api/blobs`)
 #[wasm_bindgen]
-pub fn publish_post(content: String, author_name: String) -> Result<JsValue, JsValue> {
+pub fn publish_post(
+    content: String,
+    author_name: String,
+    reply_to: Option<String>,
+    channel: Option<String>,
+    attachments: JsValue,
+) -> Result<JsValue, JsValue> {
+    if zeta_proto::grapheme_count(&content) > zeta_proto::MAX_POST_GRAPHEMES {
+        return Err(JsValue::from_str("Post too long"));
+    }
+    let channel = match channel {
+        Some(name) => Some(channel::normalize(&name).ok_or_else(|| JsValue::from_str("Invalid channel name"))?),
+        None => None,
+    };
+    let attachments: Vec<Attachment> = if attachments.is_undefined() || attachments.is_null() {
+        Vec::new()
+    } else {
+        serde_wasm_bindgen::from_value(attachments)?
+    };
+    if attachments.len() > blob::MAX_ATTACHMENTS || !attachments.iter().all(Attachment::is_valid) {
+        return Err(JsValue::from_str("Invalid attachments"));
+    }
     NODE.with(|n| {
         let mut node_ref = n.borrow_mut();
         let node = node_ref.as_mut().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
 
-        let post = Post {
+        // La conversation (et par défaut le canal) est celle du parent s'il est connu
+        let parent = reply_to.as_ref().and_then(|parent| node.posts.iter().find(|p| &p.id == parent));
+        let thread_root = reply_to.as_ref().map(|parent_id| {
+            parent.map(|p| p.thread_id().to_string()).unwrap_or_else(|| parent_id.clone())
+        });
+        let channel = channel
+            .or_else(|| parent.and_then(|p| p.channel.clone()))
+            .filter(|c| c != channel::DEFAULT_CHANNEL);
+        let mut post = Post {
             id: uuid::Uuid::new_v4().to_string(),
             author: node.peer_id.clone(),
             author_name,
             content,
             timestamp: chrono::Utc::now().timestamp(),
+            reply_to,
+            thread_root,
+            channel,
+            attachments,
+            ..Default::default()
         };
+        post.sign(&node.private_key);
 
         // Envoyer via WebSocket
         if let Some(ref ws) = node.ws {
             if ws.ready_state() == WebSocket::OPEN {
-                let msg = WsOutMessage {
-                    msg_type: "post".to_string(),
-                    content: Some(post.content.clone()),
-                    author_name: Some(post.author_name.clone()),
-                };
-                let msg_str = serde_json::to_string(&msg)
-                    .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))?;
-                ws.send_with_str(&msg_str)
+                let msg = ClientFrame::Post(PostPayload::Signed { post: post.clone() });
+                ws.send_with_str(&msg.to_json())
                     .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
             }
         }
@@ -181,6 +229,23 @@ pub fn publish_post(content: String, author_name: String) -> Result<JsValue, JsV
     })
 }
 
+/// URL HTTP d'un fichier joint sur le relay courant
+#[wasm_bindgen]
+pub fn get_blob_url(cid: String) -> Result<String, JsValue> {
+    if !blob::is_valid_cid(&cid) {
+        return Err(JsValue::from_str("Invalid cid"));
+    }
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        let relay = node.relay_urls.get(node.relay_index).ok_or_else(|| JsValue::from_str("No relay"))?;
+        // ws://hote:port/ws -> http://hote:port/api/blobs/<cid>
+        let base = relay.replacen("ws", "http", 1);
+        let base = base.strip_suffix("/ws").unwrap_or(&base);
+        Ok(format!("{}/api/blobs/{}", base, cid))
+    })
+}
+
 /// Récupère la liste des peers
 #[wasm_bindgen]
 pub fn get_peers() -> Result<JsValue, JsValue> {
@@ -202,6 +267,200 @@ pub fn get_posts() -> Result<JsValue, JsValue> {
     })
 }
 
+/// Modifie un de nos posts
+#[wasm_bindgen]
+pub fn edit_post(post_id: String, content: String) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        let edit = PostEdit::new(post_id, content, chrono::Utc::now().timestamp_millis(), &node.private_key);
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&ClientFrame::Edit { edit: edit.clone() }.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
+
+        Ok(serde_wasm_bindgen::to_value(&edit)?)
+    })
+}
+
+/// Supprime un de nos posts
+#[wasm_bindgen]
+pub fn delete_post(post_id: String) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        let delete = PostDelete::new(post_id, chrono::Utc::now().timestamp(), &node.private_key);
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&ClientFrame::Delete { delete: delete.clone() }.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
+
+        Ok(serde_wasm_bindgen::to_value(&delete)?)
+    })
+}
+
+/// Réagit à un post avec un emoji
+#[wasm_bindgen]
+pub fn react(post_id: String, emoji: String) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        let reaction = Reaction::new(post_id, emoji, &node.private_key);
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&ClientFrame::React { reaction: reaction.clone() }.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
+
+        Ok(serde_wasm_bindgen::to_value(&reaction)?)
+    })
+}
+
+/// Récupère les compteurs de réactions d'un post
+#[wasm_bindgen]
+pub fn get_reactions(post_id: String) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        let counts = node.reactions.get(&post_id).cloned().unwrap_or_default();
+        Ok(serde_wasm_bindgen::to_value(&counts)?)
+    })
+}
+
+/// Envoie un message direct chiffré pour le seul destinataire
+#[wasm_bindgen]
+pub fn send_direct_message(recipient: String, content: String) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        let message = DirectMessage::seal(&node.private_key, recipient, &content, chrono::Utc::now().timestamp())
+            .ok_or_else(|| JsValue::from_str("Invalid recipient or message too long"))?;
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&ClientFrame::Direct { message: message.clone() }.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
+
+        Ok(serde_wasm_bindgen::to_value(&message)?)
+    })
+}
+
+/// Publie notre profil signé : nom affiché, bio et référence d'avatar
+#[wasm_bindgen]
+pub fn set_profile(name: String, bio: Option<String>, avatar: Option<String>) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let mut node_ref = n.borrow_mut();
+        let node = node_ref.as_mut().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        let avatar = avatar.filter(|a| !a.is_empty());
+        let profile = Profile::new(name, bio.unwrap_or_default(), avatar, chrono::Utc::now().timestamp_millis(), &node.private_key);
+        if !profile.verify() {
+            return Err(JsValue::from_str("Invalid profile (name, bio or avatar too long)"));
+        }
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&ClientFrame::SetProfile { profile: profile.clone() }.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
+
+        node.name = profile.name.clone();
+        node.profiles.insert(profile.identity.clone(), profile.clone());
+        Ok(serde_wasm_bindgen::to_value(&profile)?)
+    })
+}
+
+/// Profil vérifié d'une identité (la nôtre par défaut), `null` s'il est inconnu
+#[wasm_bindgen]
+pub fn get_profile(peer_id: Option<String>) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        let identity = peer_id.unwrap_or_else(|| node.peer_id.clone());
+        match node.profiles.get(&identity) {
+            Some(profile) => Ok(serde_wasm_bindgen::to_value(profile)?),
+            None => Ok(JsValue::NULL),
+        }
+    })
+}
+
+/// Reçoit aussi les posts d'un canal
+#[wasm_bindgen]
+pub fn join_channel(name: String) -> Result<(), JsValue> {
+    send_channel_frame(name, true)
+}
+
+/// Ne reçoit plus les posts d'un canal
+#[wasm_bindgen]
+pub fn leave_channel(name: String) -> Result<(), JsValue> {
+    send_channel_frame(name, false)
+}
+
+/// Canaux reçus du relay
+#[wasm_bindgen]
+pub fn get_channels() -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        Ok(serde_wasm_bindgen::to_value(&node.channels)?)
+    })
+}
+
+/// Suit les posts d'un auteur
+#[wasm_bindgen]
+pub fn follow(peer_id: String) -> Result<JsValue, JsValue> {
+    send_follow(peer_id, true)
+}
+
+/// Ne suit plus les posts d'un auteur
+#[wasm_bindgen]
+pub fn unfollow(peer_id: String) -> Result<JsValue, JsValue> {
+    send_follow(peer_id, false)
+}
+
+/// Auteurs suivis, tels que confirmés par le relay
+#[wasm_bindgen]
+pub fn get_following() -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        Ok(serde_wasm_bindgen::to_value(&node.following)?)
+    })
+}
+
+/// Choisit le fil reçu du relay : "global" ou "following"
+#[wasm_bindgen]
+pub fn subscribe_feed(mode: String) -> Result<(), JsValue> {
+    let mode = match mode.as_str() {
+        "global" => FeedMode::Global,
+        "following" => FeedMode::Following,
+        other => return Err(JsValue::from_str(&format!("Unknown feed: {}", other))),
+    };
+    NODE.with(|n| {
+        let mut node_ref = n.borrow_mut();
+        let node = node_ref.as_mut().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        node.feed_mode = mode;
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&ClientFrame::Subscribe { mode }.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))
+    })
+}
+
+/// Récupère un post et l'arbre de ses réponses
+#[wasm_bindgen]
+pub fn get_thread(id: String) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+        let post = node.posts.iter().find(|p| p.id == id).ok_or_else(|| JsValue::from_str("Unknown post"))?;
+        let replies = |parent: &str| -> Vec<&Post> {
+            node.posts.iter().filter(|p| p.reply_to.as_deref() == Some(parent)).collect()
+        };
+        Ok(serde_wasm_bindgen::to_value(&ThreadNode::build(post, &replies))?)
+    })
+}
+
 /// Récupère les infos du nœud local
 #[wasm_bindgen]
 pub fn get_node_info() -> Result<JsValue, JsValue> {
@@ -213,6 +472,7 @@ pub fn get_node_info() -> Result<JsValue, JsValue> {
             "name": node.name,
             "peers_count": node.peers.len(),
             "posts_count": node.posts.len(),
+            "relays": node.relay_urls,
             "connected": node.ws.as_ref().map(|ws| ws.ready_state() == WebSocket::OPEN).unwrap_or(false)
         });
         Ok(serde_wasm_bindgen::to_value(&info)?)
@@ -241,10 +501,8 @@ fn load_or_generate_identity() -> Result<(String, [u8; 32]), JsValue> {
                 let mut private_key = [0u8; 32];
                 private_key.copy_from_slice(&key_bytes);
                 
-                // Générer le PeerId à partir de la clé publique
-                let signing_key = ed25519_dalek::SigningKey::from_bytes(&private_key);
-                let public_key = signing_key.verifying_key();
-                let peer_id = format!("12D3KooW{}", &hex::encode(public_key.as_bytes())[..32]);
+                // Dériver le PeerId à partir de la clé publique
+                let peer_id = derive_peer_id(&private_key);
                 
                 log("🔑 Clé chargée depuis localStorage");
                 return Ok((peer_id, private_key));
@@ -258,20 +516,86 @@ fn load_or_generate_identity() -> Result<(String, [u8; 32]), JsValue> {
         .map_err(|e| JsValue::from_str(&format!("Random error: {}", e)))?;
 
     // Sauvegarder
-    let key_hex = hex::encode(&private_key);
+    let key_hex = hex::encode(private_key);
     storage
         .set_item("zeta_private_key", &key_hex)
         .map_err(|_| JsValue::from_str("Failed to save key"))?;
 
-    // Générer le PeerId
-    let signing_key = ed25519_dalek::SigningKey::from_bytes(&private_key);
-    let public_key = signing_key.verifying_key();
-    let peer_id = format!("12D3KooW{}", &hex::encode(public_key.as_bytes())[..32]);
+    // Dériver le PeerId
+    let peer_id = derive_peer_id(&private_key);
 
     log("🔑 Nouvelle clé générée et sauvegardée");
     Ok((peer_id, private_key))
 }
 
+/// PeerId libp2p correspondant à la clé privée
+fn derive_peer_id(private_key: &[u8; 32]) -> String {
+    let signing_key = ed25519_dalek::SigningKey::from_bytes(private_key);
+    signing::peer_id_from_public_key(signing_key.verifying_key().as_bytes())
+}
+
+/// Répond au défi du relay en signant son nonce avec la clé du PeerId
+fn send_identify(nonce: &str) {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        if let Some(ref node) = *node_ref {
+            if let Some(ref ws) = node.ws {
+                let bytes = zeta_proto::ws::identify_bytes(nonce, &node.peer_id);
+                let msg = ClientFrame::Identify {
+                    peer_id: node.peer_id.clone(),
+                    public_key: signing::public_key_hex(&node.private_key),
+                    signature: signing::sign(&node.private_key, &bytes),
+                };
+                let _ = ws.send_with_str(&msg.to_json());
+            }
+        }
+    });
+}
+
+/// Demande au relay de rejoindre ou quitter un canal
+fn send_channel_frame(name: String, join: bool) -> Result<(), JsValue> {
+    let name = channel::normalize(&name).ok_or_else(|| JsValue::from_str("Invalid channel name"))?;
+    NODE.with(|n| {
+        let mut node_ref = n.borrow_mut();
+        let node = node_ref.as_mut().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        node.channels.retain(|c| c != &name);
+        if join {
+            node.channels.push(name.clone());
+        }
+        let frame = if join {
+            ClientFrame::JoinChannel { channel: name }
+        } else {
+            ClientFrame::LeaveChannel { channel: name }
+        };
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&frame.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))
+    })
+}
+
+/// Envoie un abonnement ou un désabonnement signé
+fn send_follow(followee: String, following: bool) -> Result<JsValue, JsValue> {
+    NODE.with(|n| {
+        let node_ref = n.borrow();
+        let node = node_ref.as_ref().ok_or_else(|| JsValue::from_str("Node not initialized"))?;
+
+        let follow = Follow::new(followee, following, chrono::Utc::now().timestamp_millis(), &node.private_key);
+        let frame = if following {
+            ClientFrame::Follow { follow: follow.clone() }
+        } else {
+            ClientFrame::Unfollow { follow: follow.clone() }
+        };
+        let ws = node.ws.as_ref().filter(|ws| ws.ready_state() == WebSocket::OPEN)
+            .ok_or_else(|| JsValue::from_str("Not connected"))?;
+        ws.send_with_str(&frame.to_json())
+            .map_err(|e| JsValue::from_str(&format!("WebSocket send error: {:?}", e)))?;
+
+        Ok(serde_wasm_bindgen::to_value(&follow)?)
+    })
+}
+
 fn connect_to_relay(relay_url: &str) -> Result<(), JsValue> {
     log(&format!("🔗 Connexion au relay: {}", relay_url));
 
@@ -310,11 +634,11 @@ fn connect_to_relay(relay_url: &str) -> Result<(), JsValue> {
         log("❌ Déconnecté du relay");
         notify_status("disconnected");
         
-        // Reconnexion automatique après 3 secondes
-        let relay_url_inner = relay_url_clone.clone();
+        // Reconnexion automatique après 3 secondes, sur le relay suivant de l'annuaire
+        let relay_url_inner = next_relay_url().unwrap_or_else(|| relay_url_clone.clone());
         let window = web_sys::window().unwrap();
         let reconnect = Closure::<dyn FnMut()>::new(move || {
-            log("🔄 Tentative de reconnexion...");
+            log(&format!("🔄 Tentative de reconnexion: {}", relay_url_inner));
             let _ = connect_to_relay(&relay_url_inner);
         });
         window.set_timeout_with_callback_and_timeout_and_arguments_0(
@@ -337,75 +661,306 @@ fn connect_to_relay(relay_url: &str) -> Result<(), JsValue> {
     Ok(())
 }
 
+/// Passe au relay suivant parmi ceux annoncés par l'annuaire
+fn next_relay_url() -> Option<String> {
+    NODE.with(|n| {
+        let mut node_ref = n.borrow_mut();
+        let node = node_ref.as_mut()?;
+        if node.relay_urls.is_empty() {
+            return None;
+        }
+        node.relay_index = (node.relay_index + 1) % node.relay_urls.len();
+        node.relay_urls.get(node.relay_index).cloned()
+    })
+}
+
 fn handle_ws_message(text: &str) {
-    if let Ok(data) = serde_json::from_str::<serde_json::Value>(text) {
-        let msg_type = data.get("type").and_then(|t| t.as_str()).unwrap_or("");
-        
-        match msg_type {
-            "init" => {
-                log("📦 État initial reçu");
-                
-                NODE.with(|n| {
-                    if let Some(ref mut node) = *n.borrow_mut() {
-                        // Mettre à jour le peer_id si fourni
-                        if let Some(pid) = data.get("peer_id").and_then(|p| p.as_str()) {
-                            node.peer_id = pid.to_string();
+    let frame = match serde_json::from_str::<ServerFrame>(text) {
+        Ok(frame) => frame,
+        Err(e) => {
+            log(&format!("⚠️ Message du relay invalide: {}", e));
+            return;
+        }
+    };
+
+    match frame {
+        ServerFrame::Init { peer_id, nonce, peers, posts, relays, reactions, profiles } => {
+            log("📦 État initial reçu");
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    // Le PeerId dérivé de la clé reste celui de la session
+                    if peer_id != node.peer_id {
+                        log(&format!("🆔 ID temporaire du relay: {}", peer_id));
+                    }
+
+                    // Mémoriser les relais de secours annoncés
+                    for relay in relays {
+                        if !node.relay_urls.contains(&relay.ws_url) {
+                            node.relay_urls.push(relay.ws_url);
                         }
-                        
-                        // Charger les peers
-                        if let Some(peers_arr) = data.get("peers").and_then(|p| p.as_array()) {
-                            node.peers.clear();
-                            for peer_val in peers_arr {
-                                if let Ok(peer) = serde_json::from_value::<PeerInfo>(peer_val.clone()) {
-                                    node.peers.insert(peer.peer_id.clone(), peer);
-                                }
-                            }
+                    }
+
+                    node.reactions = reactions;
+
+                    for profile in profiles {
+                        apply_profile(node, profile);
+                    }
+
+                    // Charger les peers
+                    node.peers = peers.into_iter().map(|peer| (peer.peer_id.clone(), peer)).collect();
+
+                    // Charger les posts
+                    node.posts.clear();
+                    for post in posts {
+                        if post.verify() {
+                            node.posts.push(post);
+                        } else {
+                            log(&format!("🚫 Post falsifié ignoré: {}", post.id));
                         }
-                        
-                        // Charger les posts
-                        if let Some(posts_arr) = data.get("posts").and_then(|p| p.as_array()) {
-                            node.posts.clear();
-                            for post_val in posts_arr {
-                                if let Ok(post) = serde_json::from_value::<Post>(post_val.clone()) {
-                                    node.posts.push(post);
-                                }
-                            }
+                    }
+                }
+            });
+
+            // Répondre au défi d'authentification
+            send_identify(&nonce);
+
+            // Redemander les canaux suivis avant la reconnexion
+            NODE.with(|n| {
+                if let Some(ref node) = *n.borrow() {
+                    if let Some(ref ws) = node.ws {
+                        for name in node.channels.iter().filter(|c| c.as_str() != channel::DEFAULT_CHANNEL) {
+                            let _ = ws.send_with_str(&ClientFrame::JoinChannel { channel: name.clone() }.to_json());
                         }
                     }
-                });
-                
-                // Notifier JS
-                notify_peers();
-                notify_posts();
+                }
+            });
+
+            // Notifier JS
+            notify_peers();
+            notify_posts();
+        }
+
+        ServerFrame::NewPost { post } => {
+            if !post.verify() {
+                log(&format!("🚫 Post falsifié ignoré: {}", post.id));
+                return;
             }
-            
-            "new_post" | "Post" => {
-                let post_data = data.get("post").or(data.get("Post")).unwrap_or(&data);
-                if let Ok(post) = serde_json::from_value::<Post>(post_data.clone()) {
-                    log(&format!("📨 Nouveau post de {}", post.author_name));
-                    
-                    NODE.with(|n| {
-                        if let Some(ref mut node) = *n.borrow_mut() {
-                            // Éviter les doublons
-                            if !node.posts.iter().any(|p| p.id == post.id) {
-                                node.posts.insert(0, post);
-                            }
+            log(&format!("📨 Nouveau post de {}", post.author_name));
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    // Éviter les doublons
+                    if !node.posts.iter().any(|p| p.id == post.id) {
+                        node.posts.insert(0, post);
+                    }
+                }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::NewPosts { posts, reactions } => {
+            log(&format!("🔁 {} post(s) rattrapé(s) par le relay", posts.len()));
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    for post in posts {
+                        if post.verify() && !node.posts.iter().any(|p| p.id == post.id) {
+                            node.posts.push(post);
                         }
-                    });
-                    
-                    notify_posts();
+                    }
+                    node.posts.sort_by_key(|p| std::cmp::Reverse(p.timestamp));
+                    node.reactions.extend(reactions);
                 }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::PostUpdated { post } => {
+            if !post.verify() {
+                log(&format!("🚫 Modification falsifiée ignorée: {}", post.id));
+                return;
             }
-            
-            "pong" => {
-                // Heartbeat OK
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    if let Some(existing) = node.posts.iter_mut().find(|p| p.id == post.id) {
+                        *existing = post;
+                    }
+                }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::PostDeleted { post_id } => {
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    node.posts.retain(|p| p.id != post_id);
+                    node.reactions.remove(&post_id);
+                }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::Reactions { post_id, counts } => {
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    node.reactions.insert(post_id, counts);
+                }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::PeerJoined { .. } | ServerFrame::PeerLeft { .. } => {
+            // La liste des peers est rechargée à chaque connexion
+        }
+
+        ServerFrame::Identified { .. } => {
+            log("🆔 Identité reconnue par le relay");
+
+            // Redemander le fil des abonnements après une reconnexion
+            NODE.with(|n| {
+                if let Some(ref node) = *n.borrow() {
+                    if let (FeedMode::Following, Some(ws)) = (node.feed_mode, &node.ws) {
+                        let _ = ws.send_with_str(&ClientFrame::Subscribe { mode: FeedMode::Following }.to_json());
+                    }
+                }
+            });
+        }
+
+        ServerFrame::Following { identity, following } => {
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    if identity == node.peer_id {
+                        node.following = following;
+                    }
+                }
+            });
+        }
+
+        ServerFrame::Direct { message } => {
+            if !message.verify() {
+                log(&format!("🚫 Message direct falsifié ignoré ({})", message.sender));
+                return;
             }
-            
-            _ => {
-                log(&format!("📩 Message reçu: {}", msg_type));
+            notify_direct(&message);
+        }
+
+        ServerFrame::Profile { profile } => {
+            if !profile.verify() {
+                log(&format!("🚫 Profil falsifié ignoré ({})", profile.identity));
+                return;
             }
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    apply_profile(node, profile);
+                }
+            });
+
+            // Les noms affichés des posts et des peers ont pu changer
+            notify_peers();
+            notify_posts();
         }
+
+        ServerFrame::Channels { channels } => {
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    node.channels = channels;
+                }
+            });
+        }
+
+        ServerFrame::Channel { channel, posts } => {
+            log(&format!("#️⃣ Canal #{} reçu ({} posts)", channel, posts.len()));
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    for post in posts {
+                        if post.verify() && !node.posts.iter().any(|p| p.id == post.id) {
+                            node.posts.push(post);
+                        }
+                    }
+                    node.posts.sort_by_key(|p| std::cmp::Reverse(p.timestamp));
+                }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::Feed { mode, posts } => {
+            log(&format!("📰 Fil {:?} reçu ({} posts)", mode, posts.len()));
+
+            NODE.with(|n| {
+                if let Some(ref mut node) = *n.borrow_mut() {
+                    node.posts = posts.into_iter().filter(Post::verify).collect();
+                }
+            });
+
+            notify_posts();
+        }
+
+        ServerFrame::Pong => {
+            // Heartbeat OK
+        }
+
+        ServerFrame::Thread { .. } => {
+            // Les conversations sont reconstruites localement par `get_thread`
+        }
+
+        ServerFrame::Error { code, message } => {
+            log(&format!("⚠️ Refus du relay ({:?}): {}", code, message));
+        }
+
+        ServerFrame::RateLimited { scope, retry_after_ms } => {
+            log(&format!("⏳ Débit limité par le relay ({:?}), réessayer dans {} ms", scope, retry_after_ms));
+        }
+    }
+}
+
+/// Retient un profil vérifié s'il est plus récent que celui connu
+fn apply_profile(node: &mut ZetaNode, profile: Profile) {
+    if !profile.verify() || node.profiles.get(&profile.identity).is_some_and(|p| p.timestamp >= profile.timestamp) {
+        return;
+    }
+    if profile.identity == node.peer_id {
+        node.name = profile.name.clone();
     }
+    if let Some(peer) = node.peers.get_mut(&profile.identity) {
+        peer.name = Some(profile.name.clone());
+    }
+    node.profiles.insert(profile.identity.clone(), profile);
+}
+
+/// Déchiffre un message direct et le remet au callback JavaScript, une seule fois
+fn notify_direct(message: &DirectMessage) {
+    NODE.with(|n| {
+        let mut node_ref = n.borrow_mut();
+        let Some(ref mut node) = *node_ref else { return };
+        if !node.directs_seen.insert(message.id().to_string()) {
+            return;
+        }
+        let Some(content) = message.open(&node.private_key) else {
+            log(&format!("⚠️ Message direct de {} indéchiffrable", message.sender));
+            return;
+        };
+        if let Some(ref callback) = node.on_direct {
+            let direct = serde_json::json!({
+                "sender": message.sender,
+                "recipient": message.recipient,
+                "timestamp": message.timestamp,
+                "content": content,
+            });
+            if let Ok(direct_js) = serde_wasm_bindgen::to_value(&direct) {
+                let _ = callback.call1(&JsValue::NULL, &direct_js);
+            }
+        }
+    });
 }
 
 fn notify_status(status: &str) {
@@ -457,7 +1012,7 @@ pub fn start_heartbeat() {
             if let Some(ref node) = *node_ref {
                 if let Some(ref ws) = node.ws {
                     if ws.ready_state() == WebSocket::OPEN {
-                        let _ = ws.send_with_str(r#"{"type":"ping"}"#);
+                        let _ = ws.send_with_str(&ClientFrame::Ping.to_json());
                     }
                 }
             }